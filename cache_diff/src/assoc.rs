@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+
+/// Compares two association lists of `(key, value)` pairs as maps
+///
+/// Used by `#[cache_diff(assoc)]`. When a key is repeated the last value wins, like inserting
+/// into a map. Returns `(key, old, now)` for every key that was added, removed, or changed,
+/// sorted by key. A missing side is `None`.
+#[allow(clippy::type_complexity)]
+pub fn assoc_changes<'a, K, V, Old, Now>(
+    old: Old,
+    now: Now,
+) -> Vec<(&'a K, Option<&'a V>, Option<&'a V>)>
+where
+    K: Ord + 'a,
+    V: PartialEq + 'a,
+    Old: IntoIterator<Item = &'a (K, V)>,
+    Now: IntoIterator<Item = &'a (K, V)>,
+{
+    let old = old
+        .into_iter()
+        .map(|(key, value)| (key, value))
+        .collect::<BTreeMap<&K, &V>>();
+    let mut now = now
+        .into_iter()
+        .map(|(key, value)| (key, value))
+        .collect::<BTreeMap<&K, &V>>();
+
+    let mut changes = Vec::new();
+    for (key, old_value) in old {
+        match now.remove(key) {
+            Some(now_value) if now_value == old_value => {}
+            now_value => changes.push((key, Some(old_value), now_value)),
+        }
+    }
+    changes.extend(
+        now.into_iter()
+            .map(|(key, now_value)| (key, None, Some(now_value))),
+    );
+    changes.sort_by_key(|(key, _, _)| *key);
+    changes
+}
@@ -7,11 +7,49 @@
 //!
 //! You can manually implement the trait, or you can use the `#[derive(CacheDiff)]` macro to automatically generate the implementation.
 //!
-//! Attributes are:
+//! Field attributes are:
 //!
-//!   - `cache_diff(rename = "<new name>")` Specify custom name for the field
-//!   - `cache_diff(ignore)` Ignores the given field
+//!   - `cache_diff(rename = "<new name>")` Specify custom name for the field, or a path to a `const &str`
+//!   - `cache_diff(ignore)` or `cache_diff(ignore = "<reason>")` Ignores the given field, a reason is recorded in `CACHE_DIFF_IGNORED`
 //!   - `cache_diff(display = <function>)` Specify a function to call to display the field
+//!   - `cache_diff(display_old = <function>, display_new = <function>)` Display the old or new value with its own function
+//!   - `cache_diff(try_display = <function>)` Display the field with a fallible function, showing `<unrenderable>` on error
+//!   - `cache_diff(on_change = "<enable|disable|both>")` Only report a boolean change in the given direction
+//!   - `cache_diff(group = "<group name>")` Report the field under a group heading via `grouped_diff`
+//!   - `cache_diff(generation)` Only report a change when the new value is greater than the old value
+//!   - `cache_diff(flatten)` Inline the differences of a field that implements `CacheDiff`
+//!   - `cache_diff(sensitive)` Report that the field changed without showing its values
+//!   - `cache_diff(context)` Don't compare the field, but show its current value whenever there are differences
+//!   - `cache_diff(weight = <number>)` Sort the field's differences by weight (highest first, default `0`) via `diff_weighted`
+//!   - `cache_diff(presence)` Only report when an `Option` field changes between `Some` and `None`, shown as `set` or `unset`
+//!   - `cache_diff(informational)` Report the field's changes, but don't count them towards `must_invalidate`
+//!   - `cache_diff(threshold = <number>)` Only report a change when the old and new values are on opposite sides of the number
+//!   - `cache_diff(debug)` Display the field with its `Debug` implementation
+//!   - `cache_diff(debug, sorted)` Compare and display `Debug` output with the entries of every `{...}` sorted, for `HashMap` and `HashSet` fields
+//!   - `cache_diff(unordered)` Compare a collection ignoring order, reporting added and removed elements
+//!   - `cache_diff(assoc)` Compare a list of `(key, value)` pairs like a map, reporting each added, removed, or changed key
+//!   - `cache_diff(round = <places>)` Round a number to the given decimal places before comparing and displaying it
+//!   - `cache_diff(truncate = <characters>)` Show at most the given number of characters of each value, followed by `…`
+//!   - `cache_diff(compare_with = <function>)` Decide if the old and new values are equal with a function instead of `PartialEq`
+//!   - `cache_diff(alias = "<old name>")` Record a previous name of the field in `CACHE_DIFF_ALIASES`, can be repeated
+//!   - `cache_diff(line = "<format>")` Render the field's difference with a custom format using `{name}`, `{old}`, and `{now}`
+//!   - `cache_diff(added)` With `compare_to`, the field doesn't exist on the other type and `diff_with` reports it as added
+//!
+//! A field's keys can be comma separated in one `#[cache_diff(...)]` or split across several, but each key may only be given once.
+//!
+//! Container (struct) attributes are:
+//!
+//!   - `cache_diff(word_separator = "<separator>")` Replace underscores in field names with the given separator instead of a space
+//!   - `cache_diff(align_names)` Pad field names to the same width so values line up
+//!   - `cache_diff(use_serde_rename)` Use a field's `#[serde(rename = "...")]` as its name when it has no `cache_diff(rename)`
+//!   - `cache_diff(compare_to = <type>)` Generate a `CacheDiffWith<type>` implementation that compares same-named fields
+//!   - `cache_diff(transition = "<word>")` Replace the `to` between old and new values, for example with `→`
+//!   - `cache_diff(custom = <function>)` Append the differences returned by a `fn(&Self, &Self) -> Vec<String>` after the field differences
+//!   - `cache_diff(gate = <field>)` When the named field changed, report only that change as `cache schema changed` and skip the other fields
+//!   - `cache_diff(removed = <field>)` With `compare_to`, a field that only exists on the other type, `diff_with` reports it as removed. Can be repeated
+//!
+//! Like field attributes, container keys can be split across several `#[cache_diff(...)]` attributes but each may only be given once,
+//! except `removed`.
 //!
 //! ## Why
 //!
@@ -33,6 +71,8 @@
 //! $ cargo add cache_diff --features bullet_stream
 //! ```
 //!
+//! To list the field names of every derived type at runtime, add the `field_registry` feature.
+//!
 //! ## Derive usage
 //!
 //! By default a `#[derive(CacheDiff)]` will generate a `diff` function that compares each field in the struct.
@@ -101,6 +141,24 @@
 //! assert_eq!(diff.join(", "), "version (`3.3.0` to `3.4.0`), distro (`Alpine` to `Ubuntu`)");
 //! ```
 //!
+//! If some changes matter more than others, give fields a `weight` and call `diff_weighted` to sort the
+//! differences from highest to lowest weight. Fields without a weight default to `0`:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     version: String,
+//!     #[cache_diff(weight = 10)]
+//!     distro: String,
+//! }
+//! let now = Metadata { version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+//! let diff = now.diff_weighted(&Metadata { version: "3.3.0".to_string(), distro: "Alpine".to_string() });
+//!
+//! assert_eq!(diff.join(", "), "distro (`Alpine` to `Ubuntu`), version (`3.3.0` to `3.4.0`)");
+//! ```
+//!
 //! ## Rename attributes
 //!
 //! If your field name is not descriptive enough, you can rename it:
@@ -119,6 +177,152 @@
 //! assert_eq!(diff.join(" "), "Ruby version (`3.3.0` to `3.4.0`)");
 //! ```
 //!
+//! To share a name between structs, the rename can be a path to a string constant:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! const RUBY_VERSION: &str = "Ruby version";
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(rename = RUBY_VERSION)]
+//!     version: String,
+//! }
+//! let now = Metadata { version: "3.4.0".to_string() };
+//! let diff = now.diff(&Metadata { version: "3.3.0".to_string() });
+//!
+//! assert_eq!(diff.join(" "), "Ruby version (`3.3.0` to `3.4.0`)");
+//! ```
+//!
+//! ## Word separator attribute
+//!
+//! By default underscores in field names are replaced with spaces. You can change the replacement
+//! for every field without a `rename` with a container attribute on the struct:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! #[cache_diff(word_separator = "-")]
+//! struct Metadata {
+//!     ruby_version: String,
+//! }
+//! let now = Metadata { ruby_version: "3.4.0".to_string() };
+//! let diff = now.diff(&Metadata { ruby_version: "3.3.0".to_string() });
+//!
+//! assert_eq!(diff.join(" "), "ruby-version (`3.3.0` to `3.4.0`)");
+//! ```
+//!
+//! ## Transition attribute
+//!
+//! Differences read `name (old to new)` by default. To replace only the `to`, for example for localization
+//! or a different style, set a `transition` on the struct:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! #[cache_diff(transition = "→")]
+//! struct Metadata {
+//!     version: String,
+//! }
+//! let now = Metadata { version: "3.4.0".to_string() };
+//! let diff = now.diff(&Metadata { version: "3.3.0".to_string() });
+//!
+//! assert_eq!(diff.join(" "), "version (`3.3.0` → `3.4.0`)");
+//! ```
+//!
+//! A field with a custom `line` uses its own format instead.
+//!
+//! ## Custom attribute
+//!
+//! For a difference that isn't tied to a single field, such as one computed from two fields together, use the
+//! container attribute `custom` with a function of the signature `fn(&Self, &Self) -> Vec<String>`. It receives
+//! the new and then the old value. The derived comparisons run first and the returned differences are appended
+//! after them, only `context` fields are shown later. It can't be combined with `compare_to`:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! #[cache_diff(custom = policy)]
+//! struct Metadata {
+//!     version: String,
+//!     #[cache_diff(ignore)]
+//!     strict: bool,
+//!     #[cache_diff(ignore)]
+//!     level: u8,
+//! }
+//!
+//! fn policy(now: &Metadata, old: &Metadata) -> Vec<String> {
+//!     if (now.strict, now.level) != (old.strict, old.level) {
+//!         vec!["rebuild forced by policy change".to_string()]
+//!     } else {
+//!         Vec::new()
+//!     }
+//! }
+//!
+//! let now = Metadata { version: "3.4.0".to_string(), strict: true, level: 2 };
+//! let diff = now.diff(&Metadata { version: "3.3.0".to_string(), strict: true, level: 1 });
+//!
+//! assert_eq!(
+//!     diff,
+//!     vec![
+//!         "version (`3.3.0` to `3.4.0`)".to_string(),
+//!         "rebuild forced by policy change".to_string()
+//!     ]
+//! );
+//! ```
+//!
+//! ## Gate attribute
+//!
+//! When a cache layout is versioned, a changed version makes every other difference meaningless. Name that
+//! field with the container attribute `gate` and a change to it is reported as a single message, no other
+//! field is compared. This applies to every method, including `diff_summary` (one of the compared fields changed) and a `compare_to`
+//! comparison. When it's unchanged the fields are compared as usual:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! #[cache_diff(gate = schema_version)]
+//! struct Metadata {
+//!     schema_version: u32,
+//!     version: String,
+//! }
+//! let now = Metadata { schema_version: 2, version: "3.4.0".to_string() };
+//!
+//! assert_eq!(
+//!     now.diff(&Metadata { schema_version: 1, version: "3.3.0".to_string() }),
+//!     vec!["cache schema changed (`1` to `2`)".to_string()]
+//! );
+//! assert_eq!(
+//!     now.diff(&Metadata { schema_version: 2, version: "3.3.0".to_string() }),
+//!     vec!["version (`3.3.0` to `3.4.0`)".to_string()]
+//! );
+//! ```
+//!
+//! ## Align names attribute
+//!
+//! When differences are logged one per line, you can pad every field name to the width of the longest
+//! name so the values line up:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! #[cache_diff(align_names)]
+//! struct Metadata {
+//!     ruby_version: String,
+//!     distro: String,
+//! }
+//! let now = Metadata { ruby_version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+//! let diff = now.diff(&Metadata { ruby_version: "3.3.0".to_string(), distro: "Alpine".to_string() });
+//!
+//! assert_eq!(diff.join("\n"), "ruby version (`3.3.0` to `3.4.0`)\ndistro       (`Alpine` to `Ubuntu`)");
+//! ```
+//!
 //! ## Ignore attributes
 //!
 //! If the struct contains fields that should not be included in the diff comparison, you can ignore them:
@@ -139,60 +343,1243 @@
 //! assert!(diff.is_empty());
 //! ```
 //!
-//! ## Handle structs missing display
+//! Marker fields, `std::marker::PhantomData<_>` and `()`, are always ignored since they have no value to compare or show.
 //!
-//! Not all structs implement the [`Display`](std::fmt::Display) trait, for example [`std::path::PathBuf`](std::path::PathBuf) requires that you call `display()` on it.
+//! To answer "why is this ignored?" give a reason, `cache_diff(ignore = "...")`. It isn't shown in the diff,
+//! the derive generates a `CACHE_DIFF_IGNORED` constant of `(field identifier, reason)` pairs for documentation
+//! tooling. A bare `ignore` is listed with an empty reason:
 //!
-//! The `#[derive(CacheDiff)]` macro will automatically handle the following conversions for you:
+//! ```rust
+//! use cache_diff::CacheDiff;
 //!
-//! - `std::path::PathBuf` (via [`std::path::Path::display`](std::path::Path::display))
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     version: String,
+//!     #[cache_diff(ignore = "set by CI, not cache-relevant")]
+//!     build_number: u64,
+//!     #[cache_diff(ignore)]
+//!     changed_by: String,
+//! }
 //!
-//! However, if you have a custom struct that does not implement [`Display`](std::fmt::Display), you can specify a function to call instead:
+//! assert_eq!(
+//!     Metadata::CACHE_DIFF_IGNORED,
+//!     &[("build_number", "set by CI, not cache-relevant"), ("changed_by", "")]
+//! );
+//! ```
+//!
+//! ## Context attributes
+//!
+//! Some fields, like a build id or timestamp, aren't a reason to invalidate the cache but are useful context
+//! when it is invalidated. Fields marked `context` are never compared, their current value is added after the
+//! other differences whenever there is at least one:
 //!
 //! ```rust
 //! use cache_diff::CacheDiff;
 //!
 //! #[derive(CacheDiff)]
 //! struct Metadata {
-//!     #[cache_diff(display = my_function)]
-//!     version: NoDisplay,
+//!     version: String,
+//!     #[cache_diff(context)]
+//!     build_id: String,
 //! }
+//! let now = Metadata { version: "3.4.0".to_string(), build_id: "abc".to_string() };
+//! let diff = now.diff(&Metadata { version: "3.3.0".to_string(), build_id: "xyz".to_string() });
+//! assert_eq!(diff.join(", "), "version (`3.3.0` to `3.4.0`), build id: `abc`");
 //!
-//! #[derive(PartialEq)]
-//! struct NoDisplay(String);
-//! fn my_function(s: &NoDisplay) -> String {
-//!     format!("custom {}", s.0)
+//! let diff = now.diff(&Metadata { version: "3.4.0".to_string(), build_id: "xyz".to_string() });
+//! assert!(diff.is_empty());
+//! ```
+//!
+//! Context is included in `diff`, `diff_weighted`, and `diff_with`. In `grouped_diff` it's added to the end of
+//! the default (`""`) group when any group has differences.
+//!
+//! ## On change attributes
+//!
+//! For boolean fields such as feature flags, sometimes only one direction of a change matters. You can
+//! report only `false` to `true` with `on_change = "enable"`, only `true` to `false` with `on_change = "disable"`,
+//! or any change with `on_change = "both"` (the default):
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(on_change = "enable")]
+//!     jemalloc: bool,
 //! }
+//! let diff = Metadata { jemalloc: true }.diff(&Metadata { jemalloc: false });
+//! assert_eq!(diff.join(" "), "jemalloc (`false` to `true`)");
 //!
-//! let now = Metadata { version: NoDisplay("3.4.0".to_string())};
-//! let diff = now.diff(&Metadata { version: NoDisplay("3.3.0".to_string())});
+//! let diff = Metadata { jemalloc: false }.diff(&Metadata { jemalloc: true });
+//! assert!(diff.is_empty());
+//! ```
 //!
-//! assert_eq!(diff.join(" "), "version (`custom 3.3.0` to `custom 3.4.0`)");
+//! ## Group attributes
+//!
+//! Related fields can be reported together under a heading. The `grouped_diff` function returns
+//! `(group_name, differences)` pairs, fields without a group are reported under an empty group name:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(group = "Operating System")]
+//!     distro: String,
+//!     version: String,
+//!     #[cache_diff(group = "Operating System")]
+//!     arch: String,
+//! }
+//! let now = Metadata { distro: "Ubuntu".to_string(), version: "3.4.0".to_string(), arch: "arm64".to_string() };
+//! let diff = now.grouped_diff(&Metadata { distro: "Alpine".to_string(), version: "3.3.0".to_string(), arch: "amd64".to_string() });
+//!
+//! assert_eq!(
+//!     diff,
+//!     vec![
+//!         ("Operating System".to_string(), vec!["distro (`Alpine` to `Ubuntu`)".to_string(), "arch (`amd64` to `arm64`)".to_string()]),
+//!         ("".to_string(), vec!["version (`3.3.0` to `3.4.0`)".to_string()]),
+//!     ]
+//! );
 //! ```
-
-/// Centralized cache invalidation logic with human readable differences
-///
-/// When a struct is used to represent values in a cache, this trait can be implemented to
-/// to determine whether or not that cache needs to be invalidated.
-pub trait CacheDiff {
-    /// Given another cache object, returns a list of differences between the two.
-    ///
-    /// If no differences, return an empty list. An empty list should indicate that the
-    /// cache should be retained (not invalidated). One or more items would indicate that
-    /// the cached value should be invalidated.
-    fn diff(&self, old: &Self) -> Vec<String>;
-
-    #[cfg(feature = "bullet_stream")]
-    fn fmt_value<T: std::fmt::Display>(&self, value: &T) -> String {
-        bullet_stream::style::value(value.to_string())
-    }
-
-    /// How values are displayed in the diff output, the default is to wrap them in backticks
-    ///
-    /// Enable ANSI colors with `features = ["bullet_stream"]`
-    #[cfg(not(feature = "bullet_stream"))]
-    fn fmt_value<T: std::fmt::Display>(&self, value: &T) -> String {
-        format!("`{}`", value)
-    }
-}
-pub use cache_diff_derive::CacheDiff;
+//!
+//! ## Generation attributes
+//!
+//! For caches keyed by a monotonic generation counter, only a newer (greater) generation should be reported.
+//! An equal or lower value is not a change:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(generation)]
+//!     generation: u64,
+//! }
+//! let diff = Metadata { generation: 2 }.diff(&Metadata { generation: 1 });
+//! assert_eq!(diff.join(" "), "generation (`1` to `2`)");
+//!
+//! let diff = Metadata { generation: 1 }.diff(&Metadata { generation: 2 });
+//! assert!(diff.is_empty());
+//! ```
+//!
+//! ## Flatten attributes
+//!
+//! A field whose type implements `CacheDiff` can have its differences inlined into the parent's list, as if
+//! its fields were declared on the parent. The nested struct's own `diff` (and value formatting) is used:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Common {
+//!     distro: String,
+//! }
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     version: String,
+//!     #[cache_diff(flatten)]
+//!     common: Common,
+//! }
+//! let now = Metadata { version: "3.4.0".to_string(), common: Common { distro: "Ubuntu".to_string() } };
+//! let diff = now.diff(&Metadata { version: "3.3.0".to_string(), common: Common { distro: "Alpine".to_string() } });
+//!
+//! assert_eq!(diff.join(", "), "version (`3.3.0` to `3.4.0`), distro (`Alpine` to `Ubuntu`)");
+//! ```
+//!
+//! Names are not prefixed, so if the nested struct and the parent both have a field with the same name, the
+//! output will contain two entries that look alike. Rename one of them to keep the output unambiguous.
+//!
+//! ## Compare to a different type
+//!
+//! When an old cache deserializes into a different type than the current struct, generate a `CacheDiffWith`
+//! implementation with `compare_to`. Each compared field is matched to the field with the same name on the other
+//! type:
+//!
+//! ```rust
+//! use cache_diff::{CacheDiff, CacheDiffWith};
+//!
+//! struct MetadataV1 {
+//!     version: String,
+//!     arch: String,
+//! }
+//!
+//! #[derive(CacheDiff)]
+//! #[cache_diff(compare_to = MetadataV1, removed = arch)]
+//! struct Metadata {
+//!     version: String,
+//!     #[cache_diff(added)]
+//!     distro: String,
+//! }
+//! let now = Metadata { version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+//! let diff = now.diff_with(&MetadataV1 { version: "3.3.0".to_string(), arch: "arm64".to_string() });
+//!
+//! assert_eq!(
+//!     diff,
+//!     vec![
+//!         "version (`3.3.0` to `3.4.0`)".to_string(),
+//!         "`distro` added".to_string(),
+//!         "`arch` removed".to_string(),
+//!     ]
+//! );
+//! ```
+//!
+//! The derive macro can't see the fields of the other type, so every compared field must exist on it with the
+//! same type, otherwise it's a compile error. Mark fields that only exist on the current struct `added`, and name
+//! fields that only exist on the other type with the container attribute `removed`. Added fields are reported
+//! in field order, removed fields after them. A field that should stay out of `diff_with` entirely can still be
+//! ignored. A `gate` field is checked as usual, a `custom` function only accepts `Self` so it can't be combined
+//! with `compare_to`.
+//!
+//! ## Sensitive attributes
+//!
+//! For values such as API tokens or signed URLs that should never appear in logs, mark the field `sensitive`.
+//! A change is still reported, but both values are replaced with `[redacted]`. The field type doesn't need to
+//! implement [`Display`](std::fmt::Display):
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(sensitive)]
+//!     token: String,
+//! }
+//! let now = Metadata { token: "secret-2".to_string() };
+//! let diff = now.diff(&Metadata { token: "secret-1".to_string() });
+//!
+//! assert_eq!(diff.join(" "), "token (`[redacted]` to `[redacted]`)");
+//! ```
+//!
+//! ## Presence attributes
+//!
+//! When only whether an `Option` is set matters, mark the field `presence`. Changing the inner value of a `Some`
+//! isn't reported, going between `Some` and `None` is reported as `set` or `unset`. The inner type doesn't need
+//! to implement [`Display`](std::fmt::Display):
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(presence)]
+//!     mirror_url: Option<String>,
+//! }
+//! let now = Metadata { mirror_url: Some("https://b.example.com".to_string()) };
+//!
+//! let diff = now.diff(&Metadata { mirror_url: Some("https://a.example.com".to_string()) });
+//! assert!(diff.is_empty());
+//!
+//! let diff = now.diff(&Metadata { mirror_url: None });
+//! assert_eq!(diff.join(" "), "mirror url (`unset` to `set`)");
+//! ```
+//!
+//! ## Informational attributes
+//!
+//! Not every difference needs to invalidate the cache. A field marked `informational` is still reported by
+//! `diff`, but `must_invalidate` only returns true when a field that isn't informational changed:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     ruby_version: String,
+//!     #[cache_diff(informational)]
+//!     build_timestamp: String,
+//! }
+//! let now = Metadata { ruby_version: "3.4.0".to_string(), build_timestamp: "2025-01-02".to_string() };
+//! let old = Metadata { ruby_version: "3.4.0".to_string(), build_timestamp: "2025-01-01".to_string() };
+//!
+//! assert_eq!(now.diff(&old).join(" "), "build timestamp (`2025-01-01` to `2025-01-02`)");
+//! assert!(!now.must_invalidate(&old));
+//! ```
+//!
+//! To report informational changes separately, `diff_partitioned` returns `(invalidating, informational)` differences.
+//!
+//! ## Threshold attributes
+//!
+//! For bucketed invalidation, such as memory crossing 512MB, use `threshold`. A change is only reported when the
+//! old and new values fall on opposite sides of it, where a value equal to the threshold counts as above.
+//! The number must be a valid literal for the field's type, for example `0.5` for an `f64`:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(threshold = 512)]
+//!     memory_mb: u32,
+//! }
+//! let now = Metadata { memory_mb: 300 };
+//!
+//! assert!(now.diff(&Metadata { memory_mb: 256 }).is_empty());
+//! assert_eq!(
+//!     now.diff(&Metadata { memory_mb: 1024 }).join(" "),
+//!     "memory mb (`1024` to `300`)"
+//! );
+//! ```
+//!
+//! ## Round attributes
+//!
+//! Noisy floats can be rounded to a number of decimal places with `round`. Values that are equal after rounding
+//! aren't reported, and a change is displayed with the rounded values:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(round = 2)]
+//!     ratio: f64,
+//! }
+//! let now = Metadata { ratio: 1.23456 };
+//!
+//! assert!(now.diff(&Metadata { ratio: 1.23460 }).is_empty());
+//! assert_eq!(now.diff(&Metadata { ratio: 2.71828 }).join(" "), "ratio (`2.72` to `1.23`)");
+//! ```
+//!
+//! ## Truncate attributes
+//!
+//! A huge value, such as a base64 blob, makes a difference unreadable. Use `truncate` to show at most that many
+//! characters of the old and new values followed by `…`. Shorter values are unchanged, and characters are counted
+//! rather than bytes so multibyte text is never split. The full values are still compared:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(truncate = 8)]
+//!     checksum: String,
+//! }
+//! let now = Metadata { checksum: "a1b2c3d4e5f6a7b8".to_string() };
+//! let diff = now.diff(&Metadata { checksum: "a1b2c3d4ffffffff".to_string() });
+//!
+//! assert_eq!(diff.join(" "), "checksum (`a1b2c3d4…` to `a1b2c3d4…`)");
+//! ```
+//!
+//! ## Unordered attributes
+//!
+//! For a collection where order doesn't matter, such as a list of enabled features, mark the field `unordered`.
+//! Reordering isn't reported, a difference lists the added and removed elements instead of the whole collection.
+//! It works with any field where `&field` iterates over elements that implement `Ord` and
+//! [`Display`](std::fmt::Display), such as `Vec`, `HashSet`, or `BTreeSet`. A `display` function is called on each element:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(unordered)]
+//!     features: Vec<String>,
+//! }
+//! let features = |names: &[&str]| Metadata {
+//!     features: names.iter().map(|name| name.to_string()).collect(),
+//! };
+//! let now = features(&["yjit", "jemalloc"]);
+//!
+//! assert!(now.diff(&features(&["jemalloc", "yjit"])).is_empty());
+//! assert_eq!(
+//!     now.diff(&features(&["jemalloc", "debug"])).join(" "),
+//!     "features (added `yjit`; removed `debug`)"
+//! );
+//! ```
+//!
+//! ## Assoc attributes
+//!
+//! Some caches store key-value pairs as a `Vec<(K, V)>` rather than a map. Mark the field `assoc` to compare it like
+//! a map, each added, removed, or changed key is reported on its own line, sorted by key. When a key is repeated the
+//! last value wins. Keys must implement `Ord` and [`Display`](std::fmt::Display), values must implement `PartialEq`
+//! and `Display`, or use a `display` function that's called on each value:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(assoc)]
+//!     env: Vec<(String, String)>,
+//! }
+//! let env = |pairs: &[(&str, &str)]| Metadata {
+//!     env: pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+//! };
+//! let now = env(&[("PATH", "/usr/bin"), ("LANG", "C")]);
+//!
+//! assert!(now.diff(&env(&[("LANG", "C"), ("PATH", "/usr/bin")])).is_empty());
+//! assert_eq!(
+//!     now.diff(&env(&[("PATH", "/bin"), ("TERM", "dumb")])),
+//!     vec![
+//!         "env `LANG` (added `C`)".to_string(),
+//!         "env `PATH` (`/bin` to `/usr/bin`)".to_string(),
+//!         "env `TERM` (removed `dumb`)".to_string(),
+//!     ]
+//! );
+//! ```
+//!
+//! ## Debug attributes
+//!
+//! A field that implements [`Debug`](std::fmt::Debug), but not [`Display`](std::fmt::Display), can be shown with
+//! `debug`. The `Debug` output of a `HashMap` or `HashSet` depends on iteration order, so add `sorted` to compare
+//! and display it with the entries of every `{...}` sorted. The field then only needs to implement `Debug`:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//! use std::collections::HashMap;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(debug, sorted)]
+//!     env: HashMap<String, String>,
+//! }
+//! let env = |pairs: &[(&str, &str)]| Metadata {
+//!     env: pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+//! };
+//! let now = env(&[("LANG", "C"), ("PATH", "/bin"), ("TERM", "dumb")]);
+//!
+//! assert!(now.diff(&env(&[("TERM", "dumb"), ("PATH", "/bin"), ("LANG", "C")])).is_empty());
+//! assert_eq!(
+//!     now.diff(&env(&[("LANG", "C")])).join(" "),
+//!     r#"env (`{"LANG": "C"}` to `{"LANG": "C", "PATH": "/bin", "TERM": "dumb"}`)"#
+//! );
+//! ```
+//!
+//! ## Compare with attributes
+//!
+//! To decide equality yourself, use `compare_with` with a function of the signature `fn(&T, &T) -> bool`
+//! that receives the old and then the new value and returns `true` when they're equal. It replaces the `!=` check
+//! entirely, so the field type doesn't need to implement [`PartialEq`](std::cmp::PartialEq):
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! // Doesn't implement `PartialEq`
+//! struct Version(String);
+//! impl std::fmt::Display for Version {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//!         write!(f, "{}", self.0)
+//!     }
+//! }
+//!
+//! fn same_major(old: &Version, now: &Version) -> bool {
+//!     old.0.split('.').next() == now.0.split('.').next()
+//! }
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(compare_with = same_major)]
+//!     version: Version,
+//! }
+//! let now = Metadata { version: Version("3.4.0".to_string()) };
+//!
+//! assert!(now.diff(&Metadata { version: Version("3.3.0".to_string()) }).is_empty());
+//! assert_eq!(
+//!     now.diff(&Metadata { version: Version("2.7.0".to_string()) }).join(" "),
+//!     "version (`2.7.0` to `3.4.0`)"
+//! );
+//! ```
+//!
+//! ## Custom line format
+//!
+//! Each difference is rendered as `{name} ({old} to {now})` by default. Use `line` to change the format of a
+//! single field, other fields keep the default. Only the placeholders `{name}`, `{old}`, and `{now}` are allowed,
+//! and any can be left out:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(line = "{name}: was {old}, now {now}")]
+//!     version: String,
+//!     distro: String,
+//! }
+//! let now = Metadata { version: "3.4.0".to_string(), distro: "ubuntu".to_string() };
+//! let diff = now.diff(&Metadata { version: "3.3.0".to_string(), distro: "alpine".to_string() });
+//!
+//! assert_eq!(
+//!     diff,
+//!     vec![
+//!         "version: was `3.3.0`, now `3.4.0`".to_string(),
+//!         "distro (`alpine` to `ubuntu`)".to_string()
+//!     ]
+//! );
+//! ```
+//!
+//! A field's `display` function is only called once a change is found, and only for the values its line shows.
+//!
+//! ## Handle structs missing display
+//!
+//! Not all structs implement the [`Display`](std::fmt::Display) trait, for example [`std::path::PathBuf`](std::path::PathBuf) requires that you call `display()` on it.
+//!
+//! The `#[derive(CacheDiff)]` macro will automatically handle the following conversions for you:
+//!
+//! - `std::path::PathBuf` (via [`std::path::Path::display`](std::path::Path::display))
+//!
+//! However, if you have a custom struct that does not implement [`Display`](std::fmt::Display), you can specify a function to call instead:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(display = my_function)]
+//!     version: NoDisplay,
+//! }
+//!
+//! #[derive(PartialEq)]
+//! struct NoDisplay(String);
+//! fn my_function(s: &NoDisplay) -> String {
+//!     format!("custom {}", s.0)
+//! }
+//!
+//! let now = Metadata { version: NoDisplay("3.4.0".to_string())};
+//! let diff = now.diff(&Metadata { version: NoDisplay("3.3.0".to_string())});
+//!
+//! assert_eq!(diff.join(" "), "version (`custom 3.3.0` to `custom 3.4.0`)");
+//! ```
+//!
+//! The function must have the signature `fn(&T) -> impl Display` where `T` is the field type. It doesn't need
+//! to allocate a `String`, any type that implements [`Display`](std::fmt::Display) can be returned:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(display = on_off)]
+//!     enabled: bool,
+//! }
+//!
+//! fn on_off(value: &bool) -> &'static str {
+//!     if *value { "on" } else { "off" }
+//! }
+//!
+//! let diff = Metadata { enabled: true }.diff(&Metadata { enabled: false });
+//!
+//! assert_eq!(diff.join(" "), "enabled (`off` to `on`)");
+//! ```
+//!
+//! When a format changed between versions, render each side with its own function using `display_old` and
+//! `display_new`. A side without one uses `display`, or the default, and they can't be combined with `display`:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     // Older caches stored versions with a `v` prefix
+//!     #[cache_diff(display_old = legacy)]
+//!     version: String,
+//! }
+//!
+//! fn legacy(version: &String) -> &str {
+//!     version.trim_start_matches('v')
+//! }
+//!
+//! let diff = Metadata { version: "3.4.0".to_string() }.diff(&Metadata { version: "v3.3.0".to_string() });
+//!
+//! assert_eq!(diff.join(" "), "version (`3.3.0` to `3.4.0`)");
+//! ```
+//!
+//! Values that can only be rendered fallibly can use `try_display` with a function of the signature
+//! `fn(&T) -> Result<impl Display, E>`. When it returns an error for the old value, the new value, or both, that
+//! side is shown as `<unrenderable>` instead. Whether the field changed is still decided by comparing the values:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(try_display = utf8)]
+//!     name: Vec<u8>,
+//! }
+//!
+//! fn utf8(bytes: &Vec<u8>) -> Result<&str, std::str::Utf8Error> {
+//!     std::str::from_utf8(bytes)
+//! }
+//!
+//! let diff = Metadata { name: b"ruby".to_vec() }.diff(&Metadata { name: vec![0xff] });
+//!
+//! assert_eq!(diff.join(" "), "name (`<unrenderable>` to `ruby`)");
+//! ```
+//!
+//! ## Runtime options
+//!
+//! Presentation can also be changed at runtime, for example for verbose and terse output from the same struct.
+//! Pass a [`CacheDiffOptions`] to `diff_with_options` to override the transition word, leave out informational
+//! fields, or limit how many characters of each value are shown:
+//!
+//! ```rust
+//! use cache_diff::{CacheDiff, CacheDiffOptions};
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     version: String,
+//!     #[cache_diff(informational)]
+//!     built_at: String,
+//! }
+//! let now = Metadata { version: "3.4.0".to_string(), built_at: "Tuesday".to_string() };
+//! let old = Metadata { version: "3.3.0".to_string(), built_at: "Monday".to_string() };
+//!
+//! let terse = CacheDiffOptions::default().with_informational(false);
+//! assert_eq!(now.diff_with_options(&old, &terse).join(" "), "version (`3.3.0` to `3.4.0`)");
+//! ```
+//!
+//! ## Field names
+//!
+//! The derive macro also generates an associated `CACHE_DIFF_FIELDS` constant on the struct. It lists the name of
+//! every compared field after `rename` and `ignore` are applied, in the order their differences are reported,
+//! so it can be used for CLI help or validating config without constructing a value:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(rename = "Ruby version")]
+//!     version: String,
+//!     #[cache_diff(ignore)]
+//!     modified_by: String,
+//!     distro: String,
+//! }
+//!
+//! assert_eq!(Metadata::CACHE_DIFF_FIELDS, &["Ruby version", "distro"]);
+//! ```
+//!
+//! Fields marked `context` are listed last. Fields marked `flatten` aren't listed, they're on the inner type's constant.
+//!
+//! When a field is renamed between versions, record its previous names with `alias`. The key can be given more than
+//! once and the derive generates a `CACHE_DIFF_ALIASES` constant mapping each field identifier to its aliases, for tooling
+//! that reconciles old and new cache schemas. Aliases aren't used when comparing:
+//!
+//! ```rust
+//! use cache_diff::CacheDiff;
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     #[cache_diff(alias = "version", alias = "ruby")]
+//!     ruby_version: String,
+//!     distro: String,
+//! }
+//!
+//! assert_eq!(Metadata::CACHE_DIFF_ALIASES, &[("ruby_version", &["version", "ruby"][..])]);
+//! ```
+//!
+//! ## Field registry
+//!
+//! With the `field_registry` feature enabled, every derived type registers its field names at startup and
+//! `cache_diff::field_registry()` returns the unique names across all of them. This is meant for tooling that
+//! needs to enumerate every known field, for example to document what can invalidate a cache.
+//!
+//! ## Testing
+//!
+//! The `assert_no_diff!` and `assert_diff!` macros compare two values and panic with the actual differences
+//! when they don't match what was expected:
+//!
+//! ```rust
+//! use cache_diff::{assert_diff, assert_no_diff, CacheDiff};
+//!
+//! #[derive(CacheDiff)]
+//! struct Metadata {
+//!     version: String,
+//! }
+//!
+//! assert_no_diff!(Metadata { version: "3.4.0".to_string() }, Metadata { version: "3.4.0".to_string() });
+//! assert_diff!(
+//!     Metadata { version: "3.4.0".to_string() },
+//!     Metadata { version: "3.3.0".to_string() },
+//!     ["version (`3.3.0` to `3.4.0`)"]
+//! );
+//! ```
+
+mod assoc;
+mod options;
+#[cfg(feature = "field_registry")]
+mod registry;
+mod report;
+mod sorted_debug;
+mod truncate;
+mod try_display;
+mod unordered;
+mod write_sink;
+
+pub use options::CacheDiffOptions;
+#[cfg(feature = "field_registry")]
+pub use registry::field_registry;
+pub use report::Report;
+
+/// Centralized cache invalidation logic with human readable differences
+///
+/// When a struct is used to represent values in a cache, this trait can be implemented to
+/// to determine whether or not that cache needs to be invalidated.
+pub trait CacheDiff {
+    /// Given another cache object, returns a list of differences between the two.
+    ///
+    /// If no differences, return an empty list. An empty list should indicate that the
+    /// cache should be retained (not invalidated). One or more items would indicate that
+    /// the cached value should be invalidated.
+    fn diff(&self, old: &Self) -> Vec<String>;
+
+    /// Returns true when there are at least `n` differences
+    ///
+    /// Useful for deciding whether "mostly changed" should mean a full rebuild. The derive macro
+    /// generates an implementation that stops comparing fields once `n` changes are found,
+    /// without building any difference strings. The default implementation counts the
+    /// result of [`CacheDiff::diff`].
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    ///     distro: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+    /// let old = Metadata { version: "3.3.0".to_string(), distro: "Ubuntu".to_string() };
+    ///
+    /// assert!(now.changed_at_least(&old, 1));
+    /// assert!(!now.changed_at_least(&old, 2));
+    /// ```
+    fn changed_at_least(&self, old: &Self, n: usize) -> bool {
+        self.changes_up_to(old, n) >= n
+    }
+
+    /// Returns the number of differences, counting stops once `limit` is reached
+    ///
+    /// Used by [`CacheDiff::changed_at_least`] and for `flatten`-ed fields so a nested struct stops
+    /// comparing as soon as the parent has found enough changes. The default implementation counts
+    /// the result of [`CacheDiff::diff`].
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    ///     distro: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+    /// let old = Metadata { version: "3.3.0".to_string(), distro: "Alpine".to_string() };
+    ///
+    /// assert_eq!(now.changes_up_to(&old, 1), 1);
+    /// assert_eq!(now.changes_up_to(&old, 5), 2);
+    /// ```
+    fn changes_up_to(&self, old: &Self, limit: usize) -> usize {
+        self.diff(old).len().min(limit)
+    }
+
+    /// Returns `(changed, total)` field counts, for messages such as "3 of 8 cache fields changed"
+    ///
+    /// The derive macro counts every compared field, ignored and `context` fields aren't included
+    /// and the fields of a `flatten`-ed struct count individually. The default implementation can't
+    /// know the number of fields and is lossy, it returns the number of differences for both.
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    ///     distro: String,
+    ///     #[cache_diff(ignore)]
+    ///     modified_by: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string(), distro: "Ubuntu".to_string(), modified_by: "Schneems".to_string() };
+    /// let old = Metadata { version: "3.3.0".to_string(), distro: "Ubuntu".to_string(), modified_by: "Richard".to_string() };
+    ///
+    /// let (changed, total) = now.diff_summary(&old);
+    /// assert_eq!(format!("{changed} of {total} cache fields changed"), "1 of 2 cache fields changed");
+    /// ```
+    fn diff_summary(&self, old: &Self) -> (usize, usize) {
+        let changed = self.diff(old).len();
+        (changed, changed)
+    }
+
+    /// Returns true when the cache should be invalidated
+    ///
+    /// Unlike [`CacheDiff::diff`], which reports every difference, fields marked
+    /// `#[cache_diff(informational)]` don't count towards invalidation. The default
+    /// implementation returns true when there are any differences.
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    ///     #[cache_diff(informational)]
+    ///     built_at: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string(), built_at: "Tuesday".to_string() };
+    /// let old = Metadata { version: "3.4.0".to_string(), built_at: "Monday".to_string() };
+    ///
+    /// assert_eq!(now.diff(&old).len(), 1);
+    /// assert!(!now.must_invalidate(&old));
+    /// ```
+    fn must_invalidate(&self, old: &Self) -> bool {
+        !self.diff(old).is_empty()
+    }
+
+    /// Returns `(invalidating, informational)` differences
+    ///
+    /// Differences from fields marked `#[cache_diff(informational)]` go in the second list and
+    /// don't need to invalidate the cache, everything else goes in the first. The default
+    /// implementation returns every difference as invalidating.
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    ///     #[cache_diff(informational)]
+    ///     built_at: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string(), built_at: "Tuesday".to_string() };
+    /// let old = Metadata { version: "3.4.0".to_string(), built_at: "Monday".to_string() };
+    ///
+    /// let (invalidating, informational) = now.diff_partitioned(&old);
+    /// assert!(invalidating.is_empty());
+    /// assert_eq!(informational, vec!["built at (`Monday` to `Tuesday`)".to_string()]);
+    /// ```
+    fn diff_partitioned(&self, old: &Self) -> (Vec<String>, Vec<String>) {
+        (self.diff(old), Vec::new())
+    }
+
+    /// Returns differences using runtime names instead of the compile time ones
+    ///
+    /// Useful for localized reports. The `names` map translates a field's identifier, such as
+    /// `ruby_version`, to the name shown in its differences. Fields missing from the map use their
+    /// default name. The default implementation ignores `names` and returns [`CacheDiff::diff`].
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    /// use std::collections::BTreeMap;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     ruby_version: String,
+    ///     distro: String,
+    /// }
+    /// let now = Metadata { ruby_version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+    /// let old = Metadata { ruby_version: "3.3.0".to_string(), distro: "Alpine".to_string() };
+    ///
+    /// let names = BTreeMap::from([("ruby_version", "Ruby-Version")]);
+    /// assert_eq!(
+    ///     now.diff_with_names(&old, &names),
+    ///     vec![
+    ///         "Ruby-Version (`3.3.0` to `3.4.0`)".to_string(),
+    ///         "distro (`Alpine` to `Ubuntu`)".to_string()
+    ///     ]
+    /// );
+    /// ```
+    fn diff_with_names(
+        &self,
+        old: &Self,
+        names: &std::collections::BTreeMap<&str, &str>,
+    ) -> Vec<String> {
+        let _ = names;
+        self.diff(old)
+    }
+
+    /// Returns differences rendered with the given [`CacheDiffOptions`]
+    ///
+    /// The derive macro generates a `diff` that calls this with [`CacheDiffOptions::default`], see it for
+    /// what can be configured. The default implementation ignores `options` and returns [`CacheDiff::diff`].
+    ///
+    /// ```rust
+    /// use cache_diff::{CacheDiff, CacheDiffOptions};
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string() };
+    /// let old = Metadata { version: "3.3.0".to_string() };
+    ///
+    /// let options = CacheDiffOptions::default().with_transition("→");
+    /// assert_eq!(
+    ///     now.diff_with_options(&old, &options),
+    ///     vec!["version (`3.3.0` → `3.4.0`)".to_string()]
+    /// );
+    /// ```
+    fn diff_with_options(&self, old: &Self, options: &CacheDiffOptions) -> Vec<String> {
+        let _ = options;
+        self.diff(old)
+    }
+
+    /// Writes each difference as a line to `writer`
+    ///
+    /// The derive macro generates an implementation that writes each difference as it's found,
+    /// without collecting them into a `Vec` first. The default implementation writes the result
+    /// of [`CacheDiff::diff`].
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string() };
+    /// let old = Metadata { version: "3.3.0".to_string() };
+    ///
+    /// let mut output = Vec::new();
+    /// now.write_diff_to(&old, &mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "version (`3.3.0` to `3.4.0`)\n");
+    /// ```
+    fn write_diff_to<W: std::io::Write>(&self, old: &Self, writer: &mut W) -> std::io::Result<()> {
+        for difference in self.diff(old) {
+            writeln!(writer, "{difference}")?;
+        }
+        Ok(())
+    }
+
+    /// Returns the differences between `self` and a default value
+    ///
+    /// Useful when there is no previous cache, for example to report what's set on first run.
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff, Default)]
+    /// struct Metadata {
+    ///     version: String,
+    ///     distro: String,
+    /// }
+    /// let diff = Metadata { version: "3.4.0".to_string(), distro: String::new() }.diff_against_default();
+    ///
+    /// assert_eq!(diff.join(" "), "version (`` to `3.4.0`)");
+    /// ```
+    fn diff_against_default(&self) -> Vec<String>
+    where
+        Self: Default,
+    {
+        self.diff(&Self::default())
+    }
+
+    /// Given another cache object, returns differences sorted by weight, highest first
+    ///
+    /// Fields with equal weight keep their declaration order. The default implementation
+    /// treats every difference as equal weight and returns [`CacheDiff::diff`] unchanged.
+    fn diff_weighted(&self, old: &Self) -> Vec<String> {
+        self.diff(old)
+    }
+
+    /// Given another cache object, returns differences organized by group heading
+    ///
+    /// Each item is a `(group_name, differences)` pair in the order the group was first declared.
+    /// Groups without any differences are omitted. Differences that don't belong to a
+    /// group are returned under an empty (`""`) group name. The default implementation
+    /// places every difference from [`CacheDiff::diff`] in that default group.
+    fn grouped_diff(&self, old: &Self) -> Vec<(String, Vec<String>)> {
+        let differences = self.diff(old);
+        if differences.is_empty() {
+            Vec::new()
+        } else {
+            vec![(String::new(), differences)]
+        }
+    }
+
+    /// Given another cache object, returns each difference as a `(plain, styled)` pair
+    ///
+    /// The styled side is the [`CacheDiff::diff`] output with `bullet_stream` ANSI colors, the
+    /// plain side is the same line with the colors removed so values keep their backticks. Use
+    /// it when one event is written to both a terminal and a log file.
+    ///
+    /// ```rust
+    /// use cache_diff::CacheDiff;
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string() };
+    /// let pairs = now.diff_plain_and_styled(&Metadata { version: "3.3.0".to_string() });
+    ///
+    /// assert_eq!(pairs[0].0, "version (`3.3.0` to `3.4.0`)");
+    /// assert_ne!(pairs[0].0, pairs[0].1);
+    /// ```
+    #[cfg(feature = "bullet_stream")]
+    fn diff_plain_and_styled(&self, old: &Self) -> Vec<(String, String)> {
+        self.diff(old)
+            .into_iter()
+            .map(|styled| (bullet_stream::strip_ansi(&styled), styled))
+            .collect()
+    }
+
+    /// How values are displayed in the diff output, the default is to wrap them in backticks
+    ///
+    /// With `features = ["bullet_stream"]` values are styled with `bullet_stream::style::value`,
+    /// which adds ANSI colors
+    fn fmt_value<T: std::fmt::Display>(&self, value: &T) -> String {
+        #[cfg(feature = "bullet_stream")]
+        {
+            bullet_stream::style::value(value.to_string())
+        }
+        #[cfg(not(feature = "bullet_stream"))]
+        {
+            format!("`{}`", value)
+        }
+    }
+
+    /// How the old (previously cached) value is displayed in the diff output, the default is [`CacheDiff::fmt_value`]
+    fn fmt_old_value<T: std::fmt::Display>(&self, value: &T) -> String {
+        self.fmt_value(value)
+    }
+
+    /// How the new (current) value is displayed in the diff output, the default is [`CacheDiff::fmt_value`]
+    ///
+    /// With `features = ["bullet_stream"]` the [`CacheDiff::fmt_value`] output is wrapped in
+    /// `bullet_stream::style::important` so it stands out from the old value. Colors already in the
+    /// output, such as those from an overridden `fmt_value`, are kept.
+    fn fmt_new_value<T: std::fmt::Display>(&self, value: &T) -> String {
+        #[cfg(feature = "bullet_stream")]
+        {
+            bullet_stream::style::important(self.fmt_value(value))
+        }
+        #[cfg(not(feature = "bullet_stream"))]
+        {
+            self.fmt_value(value)
+        }
+    }
+}
+
+/// Convenience methods for every [`CacheDiff`] type
+///
+/// Kept separate so [`CacheDiff`] only holds what an implementation can customize. Bring it into
+/// scope with `use cache_diff::CacheDiffExt;`.
+pub trait CacheDiffExt: CacheDiff {
+    /// Returns the differences, or `None` when there aren't any
+    ///
+    /// ```rust
+    /// use cache_diff::{CacheDiff, CacheDiffExt};
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string() };
+    ///
+    /// assert_eq!(now.diff_or_none(&Metadata { version: "3.4.0".to_string() }), None);
+    /// assert_eq!(
+    ///     now.diff_or_none(&Metadata { version: "3.3.0".to_string() }),
+    ///     Some(vec!["version (`3.3.0` to `3.4.0`)".to_string()])
+    /// );
+    /// ```
+    fn diff_or_none(&self, old: &Self) -> Option<Vec<String>> {
+        let differences = self.diff(old);
+        if differences.is_empty() {
+            None
+        } else {
+            Some(differences)
+        }
+    }
+
+    /// Returns the differences joined by `separator`, empty when there aren't any
+    ///
+    /// ```rust
+    /// use cache_diff::{CacheDiff, CacheDiffExt};
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    ///     distro: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+    /// let old = Metadata { version: "3.3.0".to_string(), distro: "Alpine".to_string() };
+    ///
+    /// assert_eq!(
+    ///     now.diff_joined(&old, ", "),
+    ///     "version (`3.3.0` to `3.4.0`), distro (`Alpine` to `Ubuntu`)"
+    /// );
+    /// ```
+    fn diff_joined(&self, old: &Self, separator: &str) -> String {
+        self.diff(old).join(separator)
+    }
+
+    /// Returns true when there's at least one difference
+    ///
+    /// Uses [`CacheDiff::changed_at_least`] so the derive stops at the first change without building any
+    /// difference strings.
+    ///
+    /// ```rust
+    /// use cache_diff::{CacheDiff, CacheDiffExt};
+    ///
+    /// #[derive(CacheDiff)]
+    /// struct Metadata {
+    ///     version: String,
+    /// }
+    /// let now = Metadata { version: "3.4.0".to_string() };
+    ///
+    /// assert!(now.has_changed(&Metadata { version: "3.3.0".to_string() }));
+    /// assert!(!now.has_changed(&Metadata { version: "3.4.0".to_string() }));
+    /// ```
+    fn has_changed(&self, old: &Self) -> bool {
+        self.changed_at_least(old, 1)
+    }
+}
+
+impl<T: CacheDiff + ?Sized> CacheDiffExt for T {}
+
+/// Compare against a cache struct of a different type
+///
+/// Useful when the old cache was written with an older schema that deserializes into a different type
+/// than the current metadata. Generate an implementation with `#[cache_diff(compare_to = OtherType)]`.
+///
+/// The derived implementation only compares fields by name. Each compared field must exist on `Other` with
+/// the same type or it's a compile error. A field marked `#[cache_diff(added)]` only exists on the current
+/// struct and one named with `#[cache_diff(removed = field)]` only exists on `Other`, they're reported as
+/// added or removed.
+pub trait CacheDiffWith<Other> {
+    /// Given an old cache object of a different type, returns a list of differences between the two.
+    ///
+    /// If no differences, return an empty list.
+    fn diff_with(&self, old: &Other) -> Vec<String>;
+}
+
+pub use cache_diff_derive::CacheDiff;
+
+/// Combines the differences from multiple diffs, removing exact duplicates
+///
+/// Useful when diffing against several old values where the same difference is reported more than once.
+/// Differences keep the order they were first seen in.
+///
+/// ```rust
+/// use cache_diff::CacheDiff;
+///
+/// #[derive(CacheDiff)]
+/// struct Metadata {
+///     version: String,
+///     distro: String,
+/// }
+/// let now = Metadata { version: "3.4.0".to_string(), distro: "Ubuntu".to_string() };
+/// let snapshots = [
+///     Metadata { version: "3.3.0".to_string(), distro: "Ubuntu".to_string() },
+///     Metadata { version: "3.3.0".to_string(), distro: "Alpine".to_string() },
+/// ];
+///
+/// let merged = cache_diff::merge(snapshots.iter().map(|old| now.diff(old)));
+/// assert_eq!(
+///     merged,
+///     vec![
+///         "version (`3.3.0` to `3.4.0`)".to_string(),
+///         "distro (`Alpine` to `Ubuntu`)".to_string()
+///     ]
+/// );
+/// ```
+pub fn merge(diffs: impl IntoIterator<Item = Vec<String>>) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    diffs
+        .into_iter()
+        .flatten()
+        .filter(|difference| seen.insert(difference.clone()))
+        .collect()
+}
+
+/// Asserts that two [`CacheDiff`] values have no differences
+///
+/// On failure the panic message lists every difference that was found.
+///
+/// ```rust
+/// use cache_diff::{assert_no_diff, CacheDiff};
+///
+/// #[derive(CacheDiff)]
+/// struct Metadata {
+///     version: String,
+/// }
+///
+/// assert_no_diff!(
+///     Metadata { version: "3.4.0".to_string() },
+///     Metadata { version: "3.4.0".to_string() }
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_no_diff {
+    ($new:expr, $old:expr $(,)?) => {{
+        let differences = $crate::CacheDiff::diff(&$new, &$old);
+        if !differences.is_empty() {
+            ::std::panic!(
+                "assertion failed: expected no differences, found {}:\n{}",
+                differences.len(),
+                differences
+                    .iter()
+                    .map(|difference| ::std::format!("  - {difference}"))
+                    .collect::<::std::vec::Vec<_>>()
+                    .join("\n")
+            );
+        }
+    }};
+}
+
+/// Asserts that two [`CacheDiff`] values have exactly the expected differences, in order
+///
+/// On failure the panic message shows both the expected and actual differences.
+///
+/// ```rust
+/// use cache_diff::{assert_diff, CacheDiff};
+///
+/// #[derive(CacheDiff)]
+/// struct Metadata {
+///     version: String,
+/// }
+///
+/// assert_diff!(
+///     Metadata { version: "3.4.0".to_string() },
+///     Metadata { version: "3.3.0".to_string() },
+///     ["version (`3.3.0` to `3.4.0`)"]
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_diff {
+    ($new:expr, $old:expr, [$($expected:expr),* $(,)?] $(,)?) => {{
+        let differences = $crate::CacheDiff::diff(&$new, &$old);
+        let expected: ::std::vec::Vec<::std::string::String> =
+            ::std::vec![$(::std::string::String::from($expected)),*];
+        if differences != expected {
+            ::std::panic!(
+                "assertion failed: differences do not match\n expected: {:#?}\n   actual: {:#?}",
+                expected, differences
+            );
+        }
+    }};
+}
+
+// Not public API, used by code generated from the derive macro
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::assoc::assoc_changes;
+    #[cfg(feature = "field_registry")]
+    pub use crate::registry::RegisteredFields;
+    pub use crate::sorted_debug::sorted_debug;
+    pub use crate::truncate::{truncate, Limited};
+    pub use crate::try_display::try_display;
+    pub use crate::unordered::unordered_changes;
+    pub use crate::write_sink::WriteSink;
+    #[cfg(feature = "field_registry")]
+    pub use inventory;
+}
+
+// Not public API, expands to nothing unless the `field_registry` feature is enabled
+#[cfg(feature = "field_registry")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_fields {
+    ($type_name:expr, [$($names:tt)*]) => {
+        $crate::__private::inventory::submit! {
+            $crate::__private::RegisteredFields {
+                type_name: $type_name,
+                names: &[$($names)*],
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "field_registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_fields {
+    ($type_name:expr, [$($names:tt)*]) => {};
+}
@@ -0,0 +1,86 @@
+/// Runtime presentation settings for [`CacheDiff::diff_with_options`](crate::CacheDiff::diff_with_options)
+///
+/// Lets one struct produce verbose or terse output without a second definition. The defaults
+/// match [`CacheDiff::diff`](crate::CacheDiff::diff):
+///
+/// - `transition`: `None`, use the derive's word between old and new values (`to` unless set with `cache_diff(transition = "...")`)
+/// - `include_informational`: `true`, report changes to `cache_diff(informational)` fields
+/// - `max_value_len`: `None`, show values in full
+///
+/// ```rust
+/// use cache_diff::{CacheDiff, CacheDiffOptions};
+///
+/// #[derive(CacheDiff)]
+/// struct Metadata {
+///     checksum: String,
+///     #[cache_diff(informational)]
+///     built_at: String,
+/// }
+/// let now = Metadata { checksum: "a1b2c3d4".to_string(), built_at: "Tuesday".to_string() };
+/// let old = Metadata { checksum: "e5f6a7b8".to_string(), built_at: "Monday".to_string() };
+///
+/// let terse = CacheDiffOptions::default()
+///     .with_transition("→")
+///     .with_informational(false)
+///     .with_max_value_len(4);
+/// assert_eq!(
+///     now.diff_with_options(&old, &terse),
+///     vec!["checksum (`e5f6…` → `a1b2…`)".to_string()]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheDiffOptions {
+    transition: Option<String>,
+    include_informational: bool,
+    max_value_len: Option<usize>,
+}
+
+impl Default for CacheDiffOptions {
+    fn default() -> Self {
+        Self {
+            transition: None,
+            include_informational: true,
+            max_value_len: None,
+        }
+    }
+}
+
+impl CacheDiffOptions {
+    /// Show the given word between old and new values instead of the derive's default
+    ///
+    /// Custom `cache_diff(line = "...")` formats don't show a transition and are unaffected.
+    pub fn with_transition(mut self, transition: impl Into<String>) -> Self {
+        self.transition = Some(transition.into());
+        self
+    }
+
+    /// Whether changes to `cache_diff(informational)` fields are reported
+    pub fn with_informational(mut self, include: bool) -> Self {
+        self.include_informational = include;
+        self
+    }
+
+    /// Show at most this many characters of each value followed by `…`
+    ///
+    /// When a field also has `cache_diff(truncate = N)` the smaller limit is used. Values are
+    /// still compared in full.
+    pub fn with_max_value_len(mut self, max: usize) -> Self {
+        self.max_value_len = Some(max);
+        self
+    }
+
+    /// The word shown between old and new values, if overridden
+    pub fn transition(&self) -> Option<&str> {
+        self.transition.as_deref()
+    }
+
+    /// Whether changes to `cache_diff(informational)` fields are reported
+    pub fn include_informational(&self) -> bool {
+        self.include_informational
+    }
+
+    /// The maximum number of characters shown for each value, if limited
+    pub fn max_value_len(&self) -> Option<usize> {
+        self.max_value_len
+    }
+}
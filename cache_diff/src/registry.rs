@@ -0,0 +1,45 @@
+use std::collections::BTreeSet;
+
+/// Field names of a single `#[derive(CacheDiff)]` type, submitted by the derive macro
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct RegisteredFields {
+    pub type_name: &'static str,
+    pub names: &'static [&'static str],
+}
+
+inventory::collect!(RegisteredFields);
+
+/// Returns the unique display names of every field across all derived types, sorted
+///
+/// Requires the `field_registry` feature. Every `#[derive(CacheDiff)]` type linked into the
+/// program registers its field names, so tooling can enumerate all known fields without
+/// constructing any values. Names shared by multiple types are only listed once.
+///
+/// ```rust
+/// use cache_diff::CacheDiff;
+///
+/// #[derive(CacheDiff)]
+/// struct RubyMetadata {
+///     version: String,
+///     distro: String,
+/// }
+///
+/// #[derive(CacheDiff)]
+/// struct NodeMetadata {
+///     version: String,
+/// }
+///
+/// let names = cache_diff::field_registry();
+/// assert!(names.contains(&"version"));
+/// assert!(names.contains(&"distro"));
+/// assert_eq!(names.iter().filter(|name| **name == "version").count(), 1);
+/// ```
+pub fn field_registry() -> Vec<&'static str> {
+    inventory::iter::<RegisteredFields>
+        .into_iter()
+        .flat_map(|fields| fields.names.iter().copied())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,68 @@
+use crate::CacheDiff;
+
+/// Aggregates labeled differences from multiple [`CacheDiff`] structs into a single report
+///
+/// ```rust
+/// use cache_diff::{CacheDiff, Report};
+///
+/// #[derive(CacheDiff)]
+/// struct Metadata {
+///     version: String,
+/// }
+///
+/// let mut report = Report::default();
+/// report
+///     .add(
+///         "Ruby",
+///         &Metadata { version: "3.4.0".to_string() },
+///         &Metadata { version: "3.3.0".to_string() },
+///     )
+///     .add(
+///         "Bundler",
+///         &Metadata { version: "2.6.0".to_string() },
+///         &Metadata { version: "2.6.0".to_string() },
+///     );
+///
+/// assert_eq!(
+///     report.render(),
+///     Some("Ruby:\n- version (`3.3.0` to `3.4.0`)".to_string())
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+    sections: Vec<(String, Vec<String>)>,
+}
+
+impl Report {
+    /// Diff `new` against `old` and store the differences under the given label
+    pub fn add<T: CacheDiff>(&mut self, label: &str, new: &T, old: &T) -> &mut Self {
+        self.sections.push((label.to_string(), new.diff(old)));
+        self
+    }
+
+    /// Render every section that has differences, one difference per line under its label
+    ///
+    /// Sections are rendered in the order they were added and separated by a blank line.
+    /// Returns `None` when no section has any differences.
+    pub fn render(&self) -> Option<String> {
+        let sections = self
+            .sections
+            .iter()
+            .filter(|(_, differences)| !differences.is_empty())
+            .map(|(label, differences)| {
+                let lines = differences
+                    .iter()
+                    .map(|difference| format!("- {difference}"))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                format!("{label}:\n{lines}")
+            })
+            .collect::<Vec<String>>();
+
+        if sections.is_empty() {
+            None
+        } else {
+            Some(sections.join("\n\n"))
+        }
+    }
+}
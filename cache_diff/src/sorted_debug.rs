@@ -0,0 +1,79 @@
+use std::fmt::Debug;
+
+/// Formats a value with [`Debug`] and sorts the entries of every `{...}` group
+///
+/// Used by `#[cache_diff(debug, sorted)]` so a `HashMap` or `HashSet` with the same entries
+/// always produces the same output regardless of iteration order. Lists in `[...]` and tuples
+/// in `(...)` keep their order, quoted strings and chars are left untouched.
+pub fn sorted_debug<T: Debug + ?Sized>(value: &T) -> String {
+    let chars = format!("{value:?}").chars().collect::<Vec<char>>();
+    let mut position = 0;
+    let mut output = String::new();
+    while position < chars.len() {
+        output.push_str(&next_token(&chars, &mut position));
+    }
+    output
+}
+
+/// Returns the next token, either a quoted literal, a normalized group, or a single character
+fn next_token(chars: &[char], position: &mut usize) -> String {
+    let c = chars[*position];
+    *position += 1;
+    match c {
+        '"' | '\'' => {
+            let mut token = String::from(c);
+            while let Some(&next) = chars.get(*position) {
+                *position += 1;
+                token.push(next);
+                if next == '\\' {
+                    if let Some(&escaped) = chars.get(*position) {
+                        *position += 1;
+                        token.push(escaped);
+                    }
+                } else if next == c {
+                    break;
+                }
+            }
+            token
+        }
+        '{' | '[' | '(' => {
+            let close = match c {
+                '{' => '}',
+                '[' => ']',
+                _ => ')',
+            };
+            let padded = chars.get(*position) == Some(&' ');
+            let mut entries = Vec::new();
+            let mut entry = String::new();
+            while let Some(&next) = chars.get(*position) {
+                if next == close {
+                    *position += 1;
+                    break;
+                } else if next == ',' {
+                    *position += 1;
+                    entries.push(std::mem::take(&mut entry));
+                } else {
+                    entry.push_str(&next_token(chars, position));
+                }
+            }
+            entries.push(entry);
+
+            if c == '{' {
+                let mut entries = entries
+                    .iter()
+                    .map(|entry| entry.trim())
+                    .filter(|entry| !entry.is_empty())
+                    .collect::<Vec<&str>>();
+                entries.sort_unstable();
+                if padded && !entries.is_empty() {
+                    format!("{{ {} }}", entries.join(", "))
+                } else {
+                    format!("{{{}}}", entries.join(", "))
+                }
+            } else {
+                format!("{c}{}{close}", entries.join(","))
+            }
+        }
+        _ => c.to_string(),
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt::Display;
+
+/// Renders a value, keeping at most `max` characters followed by `…` when it's longer
+///
+/// Used by `#[cache_diff(truncate = N)]`. Counts `char`s rather than bytes so multibyte
+/// UTF-8 is never split.
+pub fn truncate<T: Display + ?Sized>(value: &T, max: usize) -> String {
+    let value = value.to_string();
+    match value.char_indices().nth(max) {
+        Some((index, _)) => format!("{}…", &value[..index]),
+        None => value,
+    }
+}
+
+/// Displays a value in full, or truncated like [`truncate`] when there's a `max`
+///
+/// Used for every shown value so `CacheDiffOptions::with_max_value_len` applies without
+/// allocating when there's no limit.
+pub struct Limited<'a, T: Display + ?Sized> {
+    value: &'a T,
+    max: Option<usize>,
+}
+
+impl<'a, T: Display + ?Sized> Limited<'a, T> {
+    pub fn new(value: &'a T, max: Option<usize>) -> Self {
+        Self { value, max }
+    }
+}
+
+impl<T: Display + ?Sized> Display for Limited<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) => f.write_str(&truncate(self.value, max)),
+            None => self.value.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::fmt::Display;
+
+/// Shown in place of a value when its `try_display` function returns an error
+const UNRENDERABLE: &str = "<unrenderable>";
+
+/// Displays a successfully rendered value, or `<unrenderable>`
+pub struct TryDisplay<T>(Option<T>);
+
+impl<T: Display> Display for TryDisplay<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => f.write_str(UNRENDERABLE),
+        }
+    }
+}
+
+/// Used by `#[cache_diff(try_display = f)]`, the error is discarded
+pub fn try_display<T: Display, E>(result: Result<T, E>) -> TryDisplay<T> {
+    TryDisplay(result.ok())
+}
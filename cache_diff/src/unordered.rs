@@ -0,0 +1,44 @@
+use std::cmp::Ordering;
+
+/// Compares two collections as multisets, ignoring order
+///
+/// Used by `#[cache_diff(unordered)]`. Returns `None` when both contain the same elements,
+/// otherwise the sorted `(added, removed)` elements going from `old` to `now`.
+pub fn unordered_changes<'a, 'b, T, Old, Now>(
+    old: Old,
+    now: Now,
+) -> Option<(Vec<&'b T>, Vec<&'a T>)>
+where
+    T: Ord + 'a + 'b,
+    Old: IntoIterator<Item = &'a T>,
+    Now: IntoIterator<Item = &'b T>,
+{
+    let mut old = old.into_iter().collect::<Vec<&T>>();
+    let mut now = now.into_iter().collect::<Vec<&T>>();
+    old.sort_unstable();
+    now.sort_unstable();
+    if old == now {
+        return None;
+    }
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut old = old.into_iter().peekable();
+    let mut now = now.into_iter().peekable();
+    loop {
+        match (old.peek(), now.peek()) {
+            (Some(o), Some(n)) => match o.cmp(n) {
+                Ordering::Less => removed.extend(old.next()),
+                Ordering::Greater => added.extend(now.next()),
+                Ordering::Equal => {
+                    old.next();
+                    now.next();
+                }
+            },
+            (Some(_), None) => removed.extend(old.by_ref()),
+            (None, Some(_)) => added.extend(now.by_ref()),
+            (None, None) => break,
+        }
+    }
+    Some((added, removed))
+}
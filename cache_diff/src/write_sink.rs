@@ -0,0 +1,49 @@
+use std::io::Write;
+
+/// Writes each difference as a line as soon as it's pushed
+///
+/// Used in place of a `Vec<String>` by the derived `write_diff_to` so differences can be
+/// streamed to a writer. Has the same `push`, `extend`, and `is_empty` methods the generated
+/// code calls on a `Vec`. After the first error nothing else is written, it's returned from
+/// [`WriteSink::finish`].
+pub struct WriteSink<'a, W: Write> {
+    writer: &'a mut W,
+    written: usize,
+    error: Option<std::io::Error>,
+}
+
+impl<'a, W: Write> WriteSink<'a, W> {
+    pub fn new(writer: &'a mut W) -> Self {
+        Self {
+            writer,
+            written: 0,
+            error: None,
+        }
+    }
+
+    pub fn push(&mut self, difference: String) {
+        if self.error.is_none() {
+            match writeln!(self.writer, "{difference}") {
+                Ok(()) => self.written += 1,
+                Err(error) => self.error = Some(error),
+            }
+        }
+    }
+
+    pub fn extend(&mut self, differences: impl IntoIterator<Item = String>) {
+        for difference in differences {
+            self.push(difference);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.written == 0 && self.error.is_none()
+    }
+
+    pub fn finish(self) -> std::io::Result<()> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
@@ -19,9 +19,269 @@ use syn::{punctuated::Punctuated, Attribute, Field, Ident, Token};
 #[derive(Debug, strum::EnumIter, strum::EnumString, PartialEq, strum::Display)]
 #[allow(non_camel_case_types)]
 enum Key {
-    rename,  // #[cache_diff(rename="...")]
-    display, // #[cache_diff(display="...")]
-    ignore,  // #[cache_diff(ignore)]
+    rename,        // #[cache_diff(rename="...")]
+    display,       // #[cache_diff(display="...")]
+    ignore,        // #[cache_diff(ignore)] or #[cache_diff(ignore="...")]
+    on_change,     // #[cache_diff(on_change="...")]
+    group,         // #[cache_diff(group="...")]
+    generation,    // #[cache_diff(generation)]
+    flatten,       // #[cache_diff(flatten)]
+    weight,        // #[cache_diff(weight=...)]
+    context,       // #[cache_diff(context)]
+    sensitive,     // #[cache_diff(sensitive)]
+    line,          // #[cache_diff(line="...")]
+    presence,      // #[cache_diff(presence)]
+    informational, // #[cache_diff(informational)]
+    threshold,     // #[cache_diff(threshold=...)]
+    debug,         // #[cache_diff(debug)]
+    sorted,        // #[cache_diff(sorted)]
+    unordered,     // #[cache_diff(unordered)]
+    assoc,         // #[cache_diff(assoc)]
+    round,         // #[cache_diff(round=...)]
+    truncate,      // #[cache_diff(truncate=...)]
+    compare_with,  // #[cache_diff(compare_with=...)]
+    display_old,   // #[cache_diff(display_old=...)]
+    display_new,   // #[cache_diff(display_new=...)]
+    try_display,   // #[cache_diff(try_display=...)]
+    alias,         // #[cache_diff(alias="...")]
+    added,         // #[cache_diff(added)]
+}
+
+/// Keys that can't be given together on one field
+///
+/// Each row reads "the key cannot be combined with any of these", a pair is only listed once and
+/// is rejected whichever of the two keys comes first. Add a new key's conflicts here.
+const INCOMPATIBLE: &[(Key, &[Key])] = &[
+    (Key::generation, &[Key::on_change, Key::compare_with]),
+    (
+        Key::flatten,
+        &[
+            Key::rename,
+            Key::display,
+            Key::on_change,
+            Key::generation,
+            Key::context,
+            Key::sensitive,
+            Key::presence,
+            Key::threshold,
+            Key::debug,
+            Key::unordered,
+            Key::assoc,
+            Key::round,
+            Key::truncate,
+            Key::compare_with,
+            Key::display_old,
+            Key::display_new,
+            Key::try_display,
+            Key::line,
+        ],
+    ),
+    (
+        Key::context,
+        &[
+            Key::on_change,
+            Key::generation,
+            Key::informational,
+            Key::sensitive,
+            Key::threshold,
+            Key::unordered,
+            Key::assoc,
+            Key::compare_with,
+            Key::line,
+        ],
+    ),
+    (
+        Key::sensitive,
+        &[
+            Key::display,
+            Key::presence,
+            Key::debug,
+            Key::unordered,
+            Key::assoc,
+            Key::round,
+            Key::display_old,
+            Key::display_new,
+            Key::try_display,
+        ],
+    ),
+    (
+        Key::presence,
+        &[
+            Key::generation,
+            Key::on_change,
+            Key::display,
+            Key::threshold,
+            Key::debug,
+            Key::unordered,
+            Key::assoc,
+            Key::round,
+            Key::compare_with,
+            Key::display_old,
+            Key::display_new,
+            Key::try_display,
+        ],
+    ),
+    (
+        Key::threshold,
+        &[
+            Key::generation,
+            Key::on_change,
+            Key::sorted,
+            Key::unordered,
+            Key::assoc,
+            Key::round,
+            Key::compare_with,
+        ],
+    ),
+    (
+        Key::debug,
+        &[
+            Key::display,
+            Key::unordered,
+            Key::assoc,
+            Key::round,
+            Key::display_old,
+            Key::display_new,
+            Key::try_display,
+        ],
+    ),
+    (
+        Key::sorted,
+        &[Key::generation, Key::on_change, Key::compare_with],
+    ),
+    (
+        Key::unordered,
+        &[
+            Key::generation,
+            Key::on_change,
+            Key::line,
+            Key::assoc,
+            Key::round,
+            Key::truncate,
+            Key::compare_with,
+            Key::try_display,
+        ],
+    ),
+    (
+        Key::assoc,
+        &[
+            Key::generation,
+            Key::on_change,
+            Key::line,
+            Key::round,
+            Key::truncate,
+            Key::compare_with,
+            Key::try_display,
+        ],
+    ),
+    (
+        Key::round,
+        &[
+            Key::generation,
+            Key::on_change,
+            Key::display,
+            Key::compare_with,
+            Key::display_old,
+            Key::display_new,
+            Key::try_display,
+        ],
+    ),
+    (Key::compare_with, &[Key::on_change]),
+    (
+        Key::try_display,
+        &[Key::display, Key::display_old, Key::display_new],
+    ),
+    (Key::display_old, &[Key::display]),
+    (Key::display_new, &[Key::display]),
+    (Key::added, &[Key::context]),
+];
+
+/// Valid keys for the container (struct level) `#[cache_diff(...)]` attribute
+///
+/// Used in parsing the user input and validating it, see [`Key`] for required imports
+#[derive(Debug, strum::EnumIter, strum::EnumString, PartialEq, strum::Display)]
+#[allow(non_camel_case_types)]
+enum ContainerKey {
+    word_separator,   // #[cache_diff(word_separator="...")]
+    align_names,      // #[cache_diff(align_names)]
+    use_serde_rename, // #[cache_diff(use_serde_rename)]
+    compare_to,       // #[cache_diff(compare_to=...)]
+    transition,       // #[cache_diff(transition="...")]
+    custom,           // #[cache_diff(custom=...)]
+    gate,             // #[cache_diff(gate=...)]
+    removed,          // #[cache_diff(removed=...)]
+}
+
+/// Valid values for the `#[cache_diff(on_change = "...")]` attribute
+///
+/// Controls which direction of a boolean change is reported
+#[derive(Debug, Clone, Copy, strum::EnumIter, strum::EnumString, PartialEq, Eq, strum::Display)]
+#[allow(non_camel_case_types)]
+pub(crate) enum OnChange {
+    enable,  // Only report `false` to `true`
+    disable, // Only report `true` to `false`
+    both,    // Report any change (default behavior)
+}
+
+/// Valid placeholders for the `#[cache_diff(line = "...")]` attribute
+#[derive(Debug, Clone, Copy, strum::EnumIter, strum::EnumString, PartialEq, Eq, strum::Display)]
+#[allow(non_camel_case_types)]
+pub(crate) enum Placeholder {
+    name, // {name}
+    old,  // {old}
+    now,  // {now}
+}
+
+/// Returns the placeholders used in a `line` format string, in order of appearance
+///
+/// Supports `{{` and `}}` escapes and format specs such as `{name:<10}`, errors on any
+/// placeholder that isn't a [`Placeholder`].
+pub(crate) fn line_placeholders(line: &str) -> Result<Vec<Placeholder>, String> {
+    let mut placeholders = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let inner = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+                let key = inner.split(':').next().unwrap_or_default();
+                let placeholder = Placeholder::from_str(key).map_err(|_| {
+                    format!(
+                        "Unknown cache_diff line placeholder: `{{{key}}}`. Must be one of {}",
+                        Placeholder::iter()
+                            .map(|p| format!("`{{{p}}}`"))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    )
+                })?;
+                if !placeholders.contains(&placeholder) {
+                    placeholders.push(placeholder);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(placeholders)
+}
+
+/// Value of the `#[cache_diff(rename = ...)]` attribute
+///
+/// Either a string literal or a path to a `const &str` such as `crate::labels::RUBY_VERSION`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Rename {
+    Literal(String),
+    Path(syn::Path),
+}
+
+impl quote::ToTokens for Rename {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            Rename::Literal(value) => value.to_tokens(tokens),
+            Rename::Path(path) => path.to_tokens(tokens),
+        }
+    }
 }
 
 /// Holds the one or more attributes from `#[cache_diff(...)]` attribute configurations
@@ -30,49 +290,357 @@ enum Key {
 /// information is used to build the diff comparison.
 #[derive(Debug, PartialEq, Eq, Default)]
 pub(crate) struct CacheDiffAttributes {
-    /// When present indicates the given string should be used as a name instead of the field name
-    pub(crate) rename: Option<String>,
+    /// When present indicates the given string (or path to a string constant) should be used as a name instead of the field name
+    pub(crate) rename: Option<Rename>,
 
     /// When present indicates the given path to a function should be used to customize the display of the field value
     pub(crate) display: Option<syn::Path>,
 
-    /// When `Some` indicates the field should be ignored in the diff comparison
-    pub(crate) ignore: Option<()>,
+    /// When `Some` indicates the field should be ignored in the diff comparison, with the reason if one was given
+    pub(crate) ignore: Option<Option<String>>,
+
+    /// When present indicates a boolean field should only report changes in the given direction
+    pub(crate) on_change: Option<OnChange>,
+
+    /// When present indicates the field should be reported under the given group heading
+    pub(crate) group: Option<String>,
+
+    /// When `Some` indicates the field is a monotonic counter and only an increase is reported
+    pub(crate) generation: Option<()>,
+
+    /// When `Some` indicates the field implements `CacheDiff` and its differences are inlined into the parent
+    pub(crate) flatten: Option<()>,
+
+    /// When present indicates the field's position when differences are sorted by `diff_weighted`, higher first
+    pub(crate) weight: Option<i64>,
+
+    /// When `Some` indicates the field isn't compared, but its current value is shown whenever there are differences
+    pub(crate) context: Option<()>,
+
+    /// When `Some` indicates the field's values must never be displayed, changes are reported with redacted values
+    pub(crate) sensitive: Option<()>,
+
+    /// When present indicates the given format string should be used to render the field's difference
+    pub(crate) line: Option<syn::LitStr>,
+
+    /// When `Some` indicates an `Option` field only reports a change when it goes from `Some` to `None` or back
+    pub(crate) presence: Option<()>,
+
+    /// When `Some` indicates the field's changes are reported, but don't count towards `must_invalidate`
+    pub(crate) informational: Option<()>,
+
+    /// When present indicates a change is only reported when the old and new values are on opposite sides of this number
+    pub(crate) threshold: Option<syn::Lit>,
+
+    /// When `Some` indicates the field should be displayed with its `Debug` implementation
+    pub(crate) debug: Option<()>,
+
+    /// When `Some` indicates `debug` output should be compared after sorting the entries of every `{...}`
+    pub(crate) sorted: Option<()>,
+
+    /// When `Some` indicates a collection should be compared ignoring order, reporting added and removed elements
+    pub(crate) unordered: Option<()>,
+
+    /// When `Some` indicates a list of `(key, value)` pairs should be compared like a map, reporting each changed key
+    pub(crate) assoc: Option<()>,
+
+    /// When present indicates the value should be rounded to this many decimal places before comparison and display
+    pub(crate) round: Option<usize>,
+
+    /// When present indicates displayed values should be cut to this many characters followed by an ellipsis
+    pub(crate) truncate: Option<usize>,
+
+    /// When present indicates the given path to a function decides if the old and new values are equal instead of `PartialEq`
+    pub(crate) compare_with: Option<syn::Path>,
+
+    /// When present indicates the given path to a function should be used to display the old value instead of `display`
+    pub(crate) display_old: Option<syn::Path>,
+
+    /// When present indicates the given path to a function should be used to display the new value instead of `display`
+    pub(crate) display_new: Option<syn::Path>,
+
+    /// When present indicates the given path to a function returning a `Result` should be used to display the field value
+    pub(crate) try_display: Option<syn::Path>,
+
+    /// Previous names of the field, in the order given, the only key that may be repeated
+    pub(crate) alias: Vec<String>,
+
+    /// When `Some` indicates the field doesn't exist on the `compare_to` type and is reported as added by `diff_with`
+    pub(crate) added: Option<()>,
 }
 
 impl CacheDiffAttributes {
+    /// Whether the key was given
+    fn has(&self, key: &Key) -> bool {
+        match key {
+            Key::rename => self.rename.is_some(),
+            Key::display => self.display.is_some(),
+            Key::ignore => self.ignore.is_some(),
+            Key::on_change => self.on_change.is_some(),
+            Key::group => self.group.is_some(),
+            Key::generation => self.generation.is_some(),
+            Key::flatten => self.flatten.is_some(),
+            Key::weight => self.weight.is_some(),
+            Key::context => self.context.is_some(),
+            Key::sensitive => self.sensitive.is_some(),
+            Key::line => self.line.is_some(),
+            Key::presence => self.presence.is_some(),
+            Key::informational => self.informational.is_some(),
+            Key::threshold => self.threshold.is_some(),
+            Key::debug => self.debug.is_some(),
+            Key::sorted => self.sorted.is_some(),
+            Key::unordered => self.unordered.is_some(),
+            Key::assoc => self.assoc.is_some(),
+            Key::round => self.round.is_some(),
+            Key::truncate => self.truncate.is_some(),
+            Key::compare_with => self.compare_with.is_some(),
+            Key::display_old => self.display_old.is_some(),
+            Key::display_new => self.display_new.is_some(),
+            Key::try_display => self.try_display.is_some(),
+            Key::alias => !self.alias.is_empty(),
+            Key::added => self.added.is_some(),
+        }
+    }
+
+    /// Errors with the first pair of given keys listed in [`INCOMPATIBLE`]
+    pub(crate) fn check_compatible(&self) -> Result<(), String> {
+        for (key, others) in INCOMPATIBLE {
+            if let Some(other) = others
+                .iter()
+                .find(|&other| self.has(key) && self.has(other))
+            {
+                return Err(format!(
+                    "CacheDiff attribute `{key}` cannot be combined with `{other}`"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges every `#[cache_diff(...)]` attribute on the field, a key may only be given once
     pub(crate) fn from(field: &Field) -> syn::Result<Self> {
-        if let Some(attributes) = field
+        let mut attribute = CacheDiffAttributes::default();
+        let mut keys = Vec::new();
+        for input in field
             .attrs
             .iter()
-            .find(|&attr| attr.path().is_ident("cache_diff"))
+            .filter(|&attr| attr.path().is_ident("cache_diff"))
         {
-            CacheDiffAttributes::parse_all(attributes)
-        } else {
-            Ok(CacheDiffAttributes::default())
+            attribute.merge(input, &mut keys)?;
         }
+        Ok(attribute)
     }
 
     /// Parse all attributes inside of `#[cache_diff(...)]` and return a single CacheAttributes value
+    #[cfg(test)]
     fn parse_all(input: &Attribute) -> syn::Result<Self> {
         let mut attribute = CacheDiffAttributes::default();
+        attribute.merge(input, &mut Vec::new())?;
+        Ok(attribute)
+    }
 
+    /// Parse all attributes inside of `#[cache_diff(...)]` into `self`
+    ///
+    /// Errors when a key is already in `keys`, which holds every key seen on the field so far
+    fn merge(&mut self, input: &Attribute, keys: &mut Vec<String>) -> syn::Result<()> {
         match &input.meta {
             syn::Meta::List(meta_list) => {
-                for attr in meta_list.parse_args_with(
-                    Punctuated::<CacheDiffAttributes, Token![,]>::parse_terminated,
-                )? {
+                for (key, attr) in meta_list.parse_args_with(|input: syn::parse::ParseStream| {
+                    Punctuated::<(Ident, CacheDiffAttributes), Token![,]>::parse_terminated_with(
+                        input,
+                        |input| Ok((input.fork().parse()?, input.parse()?)),
+                    )
+                })? {
+                    if key != "alias" && keys.contains(&key.to_string()) {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            format!("Duplicate cache_diff attribute: `{key}`"),
+                        ));
+                    }
+                    keys.push(key.to_string());
                     if let Some(value) = attr.rename {
-                        attribute.rename = Some(value);
+                        self.rename = Some(value);
                     }
                     if let Some(display) = attr.display {
-                        attribute.display = Some(display);
+                        self.display = Some(display);
                     }
                     if let Some(ignore) = attr.ignore {
-                        attribute.ignore = Some(ignore);
+                        self.ignore = Some(ignore);
                     }
+                    if let Some(on_change) = attr.on_change {
+                        self.on_change = Some(on_change);
+                    }
+                    if let Some(group) = attr.group {
+                        self.group = Some(group);
+                    }
+                    if let Some(generation) = attr.generation {
+                        self.generation = Some(generation);
+                    }
+                    if let Some(flatten) = attr.flatten {
+                        self.flatten = Some(flatten);
+                    }
+                    if let Some(weight) = attr.weight {
+                        self.weight = Some(weight);
+                    }
+                    if let Some(context) = attr.context {
+                        self.context = Some(context);
+                    }
+                    if let Some(sensitive) = attr.sensitive {
+                        self.sensitive = Some(sensitive);
+                    }
+                    if let Some(line) = attr.line {
+                        self.line = Some(line);
+                    }
+                    if let Some(presence) = attr.presence {
+                        self.presence = Some(presence);
+                    }
+                    if let Some(informational) = attr.informational {
+                        self.informational = Some(informational);
+                    }
+                    if let Some(threshold) = attr.threshold {
+                        self.threshold = Some(threshold);
+                    }
+                    if let Some(debug) = attr.debug {
+                        self.debug = Some(debug);
+                    }
+                    if let Some(sorted) = attr.sorted {
+                        self.sorted = Some(sorted);
+                    }
+                    if let Some(unordered) = attr.unordered {
+                        self.unordered = Some(unordered);
+                    }
+                    if let Some(assoc) = attr.assoc {
+                        self.assoc = Some(assoc);
+                    }
+                    if let Some(round) = attr.round {
+                        self.round = Some(round);
+                    }
+                    if let Some(truncate) = attr.truncate {
+                        self.truncate = Some(truncate);
+                    }
+                    if let Some(compare_with) = attr.compare_with {
+                        self.compare_with = Some(compare_with);
+                    }
+                    if let Some(display_old) = attr.display_old {
+                        self.display_old = Some(display_old);
+                    }
+                    if let Some(display_new) = attr.display_new {
+                        self.display_new = Some(display_new);
+                    }
+                    if let Some(try_display) = attr.try_display {
+                        self.try_display = Some(try_display);
+                    }
+                    self.alias.extend(attr.alias);
+                    if let Some(added) = attr.added {
+                        self.added = Some(added);
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(syn::Error::new(
+                input.pound_token.span,
+                "Expected a list of attributes",
+            )),
+        }
+    }
+}
+
+/// Holds the attributes from a container (struct level) `#[cache_diff(...)]` attribute
+///
+/// Attributes are parsed into this struct using `CacheDiffContainerAttributes::parse_all` and then that
+/// information is used when building each field comparison.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub(crate) struct CacheDiffContainerAttributes {
+    /// When present indicates underscores in default field names should be replaced with the given string instead of a space
+    pub(crate) word_separator: Option<String>,
+
+    /// When `Some` indicates field names should be right-padded to the longest name so values line up
+    pub(crate) align_names: Option<()>,
+
+    /// When `Some` indicates a field's `#[serde(rename = "...")]` should be used as its name when there's no `cache_diff(rename)`
+    pub(crate) use_serde_rename: Option<()>,
+
+    /// When present indicates a `CacheDiffWith` implementation should be generated against the given type
+    pub(crate) compare_to: Option<syn::Path>,
+
+    /// When present indicates the given string should be shown between old and new values instead of `to`
+    pub(crate) transition: Option<String>,
+
+    /// When present indicates the given path to a function returns extra differences, reported after the fields
+    pub(crate) custom: Option<syn::Path>,
+
+    /// When present indicates a change to the named field replaces all other differences with a single message
+    pub(crate) gate: Option<Ident>,
+
+    /// Fields that only exist on the `compare_to` type, in the order given, `diff_with` reports each as removed
+    pub(crate) removed: Vec<Ident>,
+}
+
+impl CacheDiffContainerAttributes {
+    /// Merges every `#[cache_diff(...)]` attribute on the struct, a key other than `removed` may only be given once
+    pub(crate) fn from(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut attribute = CacheDiffContainerAttributes::default();
+        let mut keys = Vec::new();
+        for input in attrs
+            .iter()
+            .filter(|&attr| attr.path().is_ident("cache_diff"))
+        {
+            attribute.merge(input, &mut keys)?;
+        }
+        Ok(attribute)
+    }
+
+    /// Parse all attributes inside of a container `#[cache_diff(...)]` and return a single value
+    #[cfg(test)]
+    fn parse_all(input: &Attribute) -> syn::Result<Self> {
+        let mut attribute = CacheDiffContainerAttributes::default();
+        attribute.merge(input, &mut Vec::new())?;
+        Ok(attribute)
+    }
+
+    /// Parse all attributes inside of a container `#[cache_diff(...)]` into `self`
+    ///
+    /// Errors when a key is already in `keys`, which holds every key seen on the struct so far
+    fn merge(&mut self, input: &Attribute, keys: &mut Vec<String>) -> syn::Result<()> {
+        match &input.meta {
+            syn::Meta::List(meta_list) => {
+                for (key, attr) in meta_list.parse_args_with(|input: syn::parse::ParseStream| {
+                    Punctuated::<(Ident, CacheDiffContainerAttributes), Token![,]>::parse_terminated_with(
+                        input,
+                        |input| Ok((input.fork().parse()?, input.parse()?)),
+                    )
+                })? {
+                    if key != "removed" && keys.contains(&key.to_string()) {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            format!("Duplicate cache_diff attribute: `{key}`"),
+                        ));
+                    }
+                    keys.push(key.to_string());
+                    if let Some(word_separator) = attr.word_separator {
+                        self.word_separator = Some(word_separator);
+                    }
+                    if let Some(align_names) = attr.align_names {
+                        self.align_names = Some(align_names);
+                    }
+                    if let Some(use_serde_rename) = attr.use_serde_rename {
+                        self.use_serde_rename = Some(use_serde_rename);
+                    }
+                    if let Some(compare_to) = attr.compare_to {
+                        self.compare_to = Some(compare_to);
+                    }
+                    if let Some(transition) = attr.transition {
+                        self.transition = Some(transition);
+                    }
+                    if let Some(custom) = attr.custom {
+                        self.custom = Some(custom);
+                    }
+                    if let Some(gate) = attr.gate {
+                        self.gate = Some(gate);
+                    }
+                    self.removed.extend(attr.removed);
                 }
-                Ok(attribute)
+                Ok(())
             }
             _ => Err(syn::Error::new(
                 input.pound_token.span,
@@ -82,6 +650,95 @@ impl CacheDiffAttributes {
     }
 }
 
+impl syn::parse::Parse for CacheDiffContainerAttributes {
+    // Parse a single attribute inside of a container `#[cache_diff(...)]` attribute
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        let name_str = name.to_string();
+        let mut attribute = CacheDiffContainerAttributes::default();
+        match ContainerKey::from_str(&name_str).map_err(|_| {
+            syn::Error::new(
+                name.span(),
+                format!(
+                    "Unknown cache_diff container attribute: `{name_str}`. Must be one of {}",
+                    ContainerKey::iter()
+                        .map(|k| format!("`{k}`"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            )
+        })? {
+            ContainerKey::word_separator => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitStr>()?;
+                attribute.word_separator = Some(value.value());
+            }
+            ContainerKey::align_names => {
+                attribute.align_names = Some(());
+            }
+            ContainerKey::use_serde_rename => {
+                attribute.use_serde_rename = Some(());
+            }
+            ContainerKey::compare_to => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.compare_to = Some(input.parse()?);
+            }
+            ContainerKey::transition => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitStr>()?;
+                attribute.transition = Some(value.value());
+            }
+            ContainerKey::custom => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.custom = Some(input.parse()?);
+            }
+            ContainerKey::gate => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.gate = Some(input.parse()?);
+            }
+            ContainerKey::removed => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.removed.push(input.parse()?);
+            }
+        }
+        Ok(attribute)
+    }
+}
+
+/// Returns the value of a field's `#[serde(rename = "...")]` attribute if there is one
+///
+/// Other serde keys, including the `rename(serialize = "...", deserialize = "...")` form, are ignored
+pub(crate) fn serde_rename(field: &Field) -> syn::Result<Option<String>> {
+    let mut rename = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|&attr| attr.path().is_ident("serde"))
+    {
+        if let syn::Meta::List(meta_list) = &attr.meta {
+            for meta in
+                meta_list.parse_args_with(Punctuated::<syn::Meta, Token![,]>::parse_terminated)?
+            {
+                if let syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    value:
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(value),
+                            ..
+                        }),
+                    ..
+                }) = meta
+                {
+                    if path.is_ident("rename") {
+                        rename = Some(value.value());
+                    }
+                }
+            }
+        }
+    }
+    Ok(rename)
+}
+
 impl syn::parse::Parse for CacheDiffAttributes {
     // Parse a single attribute inside of a `#[cache_diff(...)]` attribute
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
@@ -102,15 +759,138 @@ impl syn::parse::Parse for CacheDiffAttributes {
         })? {
             Key::rename => {
                 input.parse::<syn::Token![=]>()?;
-                let value = input.parse::<syn::LitStr>()?;
-                attribute.rename = Some(value.value());
+                if input.peek(syn::LitStr) {
+                    let value = input.parse::<syn::LitStr>()?;
+                    attribute.rename = Some(Rename::Literal(value.value()));
+                } else {
+                    attribute.rename = Some(Rename::Path(input.parse()?));
+                }
             }
             Key::display => {
                 input.parse::<syn::Token![=]>()?;
                 attribute.display = Some(input.parse()?);
             }
             Key::ignore => {
-                attribute.ignore = Some(());
+                if input.peek(syn::Token![=]) {
+                    input.parse::<syn::Token![=]>()?;
+                    let value = input.parse::<syn::LitStr>()?;
+                    attribute.ignore = Some(Some(value.value()));
+                } else {
+                    attribute.ignore = Some(None);
+                }
+            }
+            Key::on_change => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitStr>()?;
+                let on_change = OnChange::from_str(&value.value()).map_err(|_| {
+                    syn::Error::new(
+                        value.span(),
+                        format!(
+                            "Unknown on_change value: `{}`. Must be one of {}",
+                            value.value(),
+                            OnChange::iter()
+                                .map(|k| format!("`{k}`"))
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        ),
+                    )
+                })?;
+                attribute.on_change = Some(on_change);
+            }
+            Key::group => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitStr>()?;
+                attribute.group = Some(value.value());
+            }
+            Key::generation => {
+                attribute.generation = Some(());
+            }
+            Key::flatten => {
+                attribute.flatten = Some(());
+            }
+            Key::weight => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitInt>()?;
+                attribute.weight = Some(value.base10_parse()?);
+            }
+            Key::context => {
+                attribute.context = Some(());
+            }
+            Key::sensitive => {
+                attribute.sensitive = Some(());
+            }
+            Key::line => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitStr>()?;
+                line_placeholders(&value.value())
+                    .map_err(|message| syn::Error::new(value.span(), message))?;
+                attribute.line = Some(value);
+            }
+            Key::presence => {
+                attribute.presence = Some(());
+            }
+            Key::informational => {
+                attribute.informational = Some(());
+            }
+            Key::threshold => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::Lit>()?;
+                match value {
+                    syn::Lit::Int(_) | syn::Lit::Float(_) => {}
+                    _ => {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "Expected a number for cache_diff `threshold`, for example `threshold = 512`",
+                        ))
+                    }
+                }
+                attribute.threshold = Some(value);
+            }
+            Key::debug => {
+                attribute.debug = Some(());
+            }
+            Key::sorted => {
+                attribute.sorted = Some(());
+            }
+            Key::unordered => {
+                attribute.unordered = Some(());
+            }
+            Key::assoc => {
+                attribute.assoc = Some(());
+            }
+            Key::round => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitInt>()?;
+                attribute.round = Some(value.base10_parse()?);
+            }
+            Key::truncate => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitInt>()?;
+                attribute.truncate = Some(value.base10_parse()?);
+            }
+            Key::compare_with => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.compare_with = Some(input.parse()?);
+            }
+            Key::display_old => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.display_old = Some(input.parse()?);
+            }
+            Key::display_new => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.display_new = Some(input.parse()?);
+            }
+            Key::try_display => {
+                input.parse::<syn::Token![=]>()?;
+                attribute.try_display = Some(input.parse()?);
+            }
+            Key::alias => {
+                input.parse::<syn::Token![=]>()?;
+                let value = input.parse::<syn::LitStr>()?;
+                attribute.alias.push(value.value());
+            }
+            Key::added => {
+                attribute.added = Some(());
             }
         }
         Ok(attribute)
@@ -127,7 +907,21 @@ mod test {
             #[cache_diff(rename="Ruby version")]
         };
         let expected = CacheDiffAttributes {
-            rename: Some("Ruby version".to_string()),
+            rename: Some(Rename::Literal("Ruby version".to_string())),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_rename_path() {
+        let input = syn::parse_quote! {
+            #[cache_diff(rename = crate::labels::RUBY_VERSION)]
+        };
+        let expected = CacheDiffAttributes {
+            rename: Some(Rename::Path(
+                syn::parse_str("crate::labels::RUBY_VERSION").unwrap(),
+            )),
             ..Default::default()
         };
         assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
@@ -151,22 +945,645 @@ mod test {
             #[cache_diff(ignore)]
         };
         let expected = CacheDiffAttributes {
-            ignore: Some(()),
+            ignore: Some(None),
             ..Default::default()
         };
         assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
     }
 
     #[test]
-    fn test_parse_all_unknown() {
+    fn test_parse_all_ignore_reason() {
         let input = syn::parse_quote! {
-            #[cache_diff(unknown = "IDK")]
+            #[cache_diff(ignore = "set by CI, not cache-relevant")]
         };
-        let result = CacheDiffAttributes::parse_all(&input);
+        let expected = CacheDiffAttributes {
+            ignore: Some(Some("set by CI, not cache-relevant".to_string())),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_incompatible_pairs_listed_once() {
+        let mut seen = Vec::new();
+        for (key, others) in INCOMPATIBLE {
+            for other in *others {
+                assert_ne!(key, other);
+                let mut pair = [key.to_string(), other.to_string()];
+                pair.sort();
+                assert!(!seen.contains(&pair), "{pair:?} is listed twice");
+                seen.push(pair);
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_compatible_either_order() {
+        let inputs: [Attribute; 2] = [
+            syn::parse_quote! { #[cache_diff(unordered, line = "{name}")] },
+            syn::parse_quote! { #[cache_diff(line = "{name}", unordered)] },
+        ];
+        for input in inputs {
+            assert_eq!(
+                CacheDiffAttributes::parse_all(&input)
+                    .unwrap()
+                    .check_compatible(),
+                Err("CacheDiff attribute `unordered` cannot be combined with `line`".to_string())
+            );
+        }
+
+        let input = syn::parse_quote! {
+            #[cache_diff(assoc, sensitive)]
+        };
+        assert_eq!(
+            CacheDiffAttributes::parse_all(&input)
+                .unwrap()
+                .check_compatible(),
+            Err("CacheDiff attribute `sensitive` cannot be combined with `assoc`".to_string())
+        );
+
+        let input = syn::parse_quote! {
+            #[cache_diff(rename = "Ruby", group = "Ruby", informational, truncate = 8)]
+        };
+        assert_eq!(
+            CacheDiffAttributes::parse_all(&input)
+                .unwrap()
+                .check_compatible(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_from_multiple_attributes() {
+        let field: Field = syn::parse_quote! {
+            #[cache_diff(rename = "Ruby version")]
+            #[serde(rename = "ruby")]
+            #[cache_diff(display = my_function, informational)]
+            version: String
+        };
+        let expected = CacheDiffAttributes {
+            rename: Some(Rename::Literal("Ruby version".to_string())),
+            display: Some(syn::parse_str("my_function").unwrap()),
+            informational: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::from(&field).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_from_duplicate_key() {
+        let field: Field = syn::parse_quote! {
+            #[cache_diff(rename = "Ruby version")]
+            #[cache_diff(rename = "Ruby")]
+            version: String
+        };
+        let result = CacheDiffAttributes::from(&field);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            "Duplicate cache_diff attribute: `rename`"
+        );
+
+        let input = syn::parse_quote! {
+            #[cache_diff(ignore, ignore)]
+        };
+        let result = CacheDiffAttributes::parse_all(&input);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            "Duplicate cache_diff attribute: `ignore`"
+        );
+    }
+
+    #[test]
+    fn test_parse_all_unknown() {
+        let input = syn::parse_quote! {
+            #[cache_diff(unknown = "IDK")]
+        };
+        let result = CacheDiffAttributes::parse_all(&input);
+        assert!(result.is_err(), "Expected an error, got {:?}", result);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            r#"Unknown cache_diff attribute: `unknown`. Must be one of `rename`, `display`, `ignore`, `on_change`, `group`, `generation`, `flatten`, `weight`, `context`, `sensitive`, `line`, `presence`, `informational`, `threshold`, `debug`, `sorted`, `unordered`, `assoc`, `round`, `truncate`, `compare_with`, `display_old`, `display_new`, `try_display`, `alias`, `added`"#
+        );
+    }
+
+    #[test]
+    fn test_parse_all_on_change() {
+        let input = syn::parse_quote! {
+            #[cache_diff(on_change = "enable")]
+        };
+        let expected = CacheDiffAttributes {
+            on_change: Some(OnChange::enable),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_group() {
+        let input = syn::parse_quote! {
+            #[cache_diff(group = "Operating System")]
+        };
+        let expected = CacheDiffAttributes {
+            group: Some("Operating System".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_generation() {
+        let input = syn::parse_quote! {
+            #[cache_diff(generation)]
+        };
+        let expected = CacheDiffAttributes {
+            generation: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_flatten() {
+        let input = syn::parse_quote! {
+            #[cache_diff(flatten)]
+        };
+        let expected = CacheDiffAttributes {
+            flatten: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_weight() {
+        let input = syn::parse_quote! {
+            #[cache_diff(weight = -10)]
+        };
+        let expected = CacheDiffAttributes {
+            weight: Some(-10),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_context() {
+        let input = syn::parse_quote! {
+            #[cache_diff(context)]
+        };
+        let expected = CacheDiffAttributes {
+            context: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_sensitive() {
+        let input = syn::parse_quote! {
+            #[cache_diff(sensitive)]
+        };
+        let expected = CacheDiffAttributes {
+            sensitive: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_line() {
+        let input = syn::parse_quote! {
+            #[cache_diff(line = "{name}: was {old}, now {now}")]
+        };
+        let expected = CacheDiffAttributes {
+            line: Some(syn::parse_quote!("{name}: was {old}, now {now}")),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_line_unknown_placeholder() {
+        let input = syn::parse_quote! {
+            #[cache_diff(line = "{name} changed to {new}")]
+        };
+        let result = CacheDiffAttributes::parse_all(&input);
+        assert!(result.is_err(), "Expected an error, got {:?}", result);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            r#"Unknown cache_diff line placeholder: `{new}`. Must be one of `{name}`, `{old}`, `{now}`"#
+        );
+    }
+
+    #[test]
+    fn test_parse_all_presence() {
+        let input = syn::parse_quote! {
+            #[cache_diff(presence)]
+        };
+        let expected = CacheDiffAttributes {
+            presence: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_informational() {
+        let input = syn::parse_quote! {
+            #[cache_diff(informational)]
+        };
+        let expected = CacheDiffAttributes {
+            informational: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_added() {
+        let input = syn::parse_quote! {
+            #[cache_diff(added)]
+        };
+        let expected = CacheDiffAttributes {
+            added: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_threshold() {
+        let input = syn::parse_quote! {
+            #[cache_diff(threshold = 512)]
+        };
+        let expected = CacheDiffAttributes {
+            threshold: Some(syn::parse_quote!(512)),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+
+        let input = syn::parse_quote! {
+            #[cache_diff(threshold = 0.5)]
+        };
+        let expected = CacheDiffAttributes {
+            threshold: Some(syn::parse_quote!(0.5)),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_threshold_not_a_number() {
+        let input = syn::parse_quote! {
+            #[cache_diff(threshold = "512")]
+        };
+        let result = CacheDiffAttributes::parse_all(&input);
+        assert!(result.is_err(), "Expected an error, got {:?}", result);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            r#"Expected a number for cache_diff `threshold`, for example `threshold = 512`"#
+        );
+    }
+
+    #[test]
+    fn test_parse_all_debug_sorted() {
+        let input = syn::parse_quote! {
+            #[cache_diff(debug, sorted)]
+        };
+        let expected = CacheDiffAttributes {
+            debug: Some(()),
+            sorted: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_unordered() {
+        let input = syn::parse_quote! {
+            #[cache_diff(unordered)]
+        };
+        let expected = CacheDiffAttributes {
+            unordered: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_assoc() {
+        let input = syn::parse_quote! {
+            #[cache_diff(assoc)]
+        };
+        let expected = CacheDiffAttributes {
+            assoc: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_round() {
+        let input = syn::parse_quote! {
+            #[cache_diff(round = 2)]
+        };
+        let expected = CacheDiffAttributes {
+            round: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_truncate() {
+        let input = syn::parse_quote! {
+            #[cache_diff(truncate = 40)]
+        };
+        let expected = CacheDiffAttributes {
+            truncate: Some(40),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_display_old_new() {
+        let input = syn::parse_quote! {
+            #[cache_diff(display_old = from_legacy, display_new = crate::fmt::current)]
+        };
+        let expected = CacheDiffAttributes {
+            display_old: Some(syn::parse_str("from_legacy").unwrap()),
+            display_new: Some(syn::parse_str("crate::fmt::current").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_try_display() {
+        let input = syn::parse_quote! {
+            #[cache_diff(try_display = parse_version)]
+        };
+        let expected = CacheDiffAttributes {
+            try_display: Some(syn::parse_str("parse_version").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_alias() {
+        let input = syn::parse_quote! {
+            #[cache_diff(alias = "ruby_version", alias = "version")]
+        };
+        let expected = CacheDiffAttributes {
+            alias: vec!["ruby_version".to_string(), "version".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+
+        let field: Field = syn::parse_quote! {
+            #[cache_diff(alias = "ruby_version")]
+            #[cache_diff(alias = "version", rename = "Ruby")]
+            ruby: String
+        };
+        let expected = CacheDiffAttributes {
+            rename: Some(Rename::Literal("Ruby".to_string())),
+            alias: vec!["ruby_version".to_string(), "version".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::from(&field).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_all_compare_with() {
+        let input = syn::parse_quote! {
+            #[cache_diff(compare_with = same_major)]
+        };
+        let expected = CacheDiffAttributes {
+            compare_with: Some(syn::parse_str("same_major").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(CacheDiffAttributes::parse_all(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_line_placeholders() {
+        assert_eq!(
+            line_placeholders("{{literal}} {now:>5} then {name} and {now}"),
+            Ok(vec![Placeholder::now, Placeholder::name])
+        );
+        assert_eq!(line_placeholders("nothing here"), Ok(vec![]));
+        assert!(line_placeholders("positional {}").is_err());
+    }
+
+    #[test]
+    fn test_parse_all_on_change_unknown() {
+        let input = syn::parse_quote! {
+            #[cache_diff(on_change = "sometimes")]
+        };
+        let result = CacheDiffAttributes::parse_all(&input);
+        assert!(result.is_err(), "Expected an error, got {:?}", result);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            r#"Unknown on_change value: `sometimes`. Must be one of `enable`, `disable`, `both`"#
+        );
+    }
+
+    #[test]
+    fn test_parse_all_container_word_separator() {
+        let input = syn::parse_quote! {
+            #[cache_diff(word_separator = "-")]
+        };
+        let expected = CacheDiffContainerAttributes {
+            word_separator: Some("-".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::parse_all(&input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_all_container_transition() {
+        let input = syn::parse_quote! {
+            #[cache_diff(transition = "→")]
+        };
+        let expected = CacheDiffContainerAttributes {
+            transition: Some("→".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::parse_all(&input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_all_container_align_names() {
+        let input = syn::parse_quote! {
+            #[cache_diff(align_names)]
+        };
+        let expected = CacheDiffContainerAttributes {
+            align_names: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::parse_all(&input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_all_container_use_serde_rename() {
+        let input = syn::parse_quote! {
+            #[cache_diff(use_serde_rename)]
+        };
+        let expected = CacheDiffContainerAttributes {
+            use_serde_rename: Some(()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::parse_all(&input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_all_container_compare_to() {
+        let input = syn::parse_quote! {
+            #[cache_diff(compare_to = crate::v1::Metadata)]
+        };
+        let expected = CacheDiffContainerAttributes {
+            compare_to: Some(syn::parse_str("crate::v1::Metadata").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::parse_all(&input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_all_container_custom() {
+        let input = syn::parse_quote! {
+            #[cache_diff(custom = policy_changes)]
+        };
+        let expected = CacheDiffContainerAttributes {
+            custom: Some(syn::parse_str("policy_changes").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::parse_all(&input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_all_container_gate() {
+        let input = syn::parse_quote! {
+            #[cache_diff(gate = schema_version)]
+        };
+        let expected = CacheDiffContainerAttributes {
+            gate: Some(syn::parse_str("schema_version").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::parse_all(&input).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_container_from_repeated_removed() {
+        let attrs: Vec<Attribute> = vec![
+            syn::parse_quote! { #[cache_diff(compare_to = OldMetadata, removed = arch)] },
+            syn::parse_quote! { #[cache_diff(removed = distro)] },
+        ];
+        let expected = CacheDiffContainerAttributes {
+            compare_to: Some(syn::parse_str("OldMetadata").unwrap()),
+            removed: vec![
+                syn::parse_str("arch").unwrap(),
+                syn::parse_str("distro").unwrap(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::from(&attrs).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_container_from_multiple_attributes() {
+        let attrs: Vec<Attribute> = vec![
+            syn::parse_quote! { #[cache_diff(align_names)] },
+            syn::parse_quote! { #[derive(Debug)] },
+            syn::parse_quote! { #[cache_diff(transition = "→", gate = schema_version)] },
+        ];
+        let expected = CacheDiffContainerAttributes {
+            align_names: Some(()),
+            transition: Some("→".to_string()),
+            gate: Some(syn::parse_str("schema_version").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CacheDiffContainerAttributes::from(&attrs).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_container_from_duplicate_key() {
+        let attrs: Vec<Attribute> = vec![
+            syn::parse_quote! { #[cache_diff(transition = "→")] },
+            syn::parse_quote! { #[cache_diff(transition = "->")] },
+        ];
+        let result = CacheDiffContainerAttributes::from(&attrs);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            "Duplicate cache_diff attribute: `transition`"
+        );
+
+        let input = syn::parse_quote! {
+            #[cache_diff(gate = schema_version, gate = version)]
+        };
+        let result = CacheDiffContainerAttributes::parse_all(&input);
+        assert_eq!(
+            format!("{}", result.err().unwrap()),
+            "Duplicate cache_diff attribute: `gate`"
+        );
+    }
+
+    #[test]
+    fn test_serde_rename() {
+        let field: Field = syn::parse_quote! {
+            #[serde(default, rename = "rubyVersion", with = "my_module")]
+            ruby_version: String
+        };
+        assert_eq!(
+            serde_rename(&field).unwrap(),
+            Some("rubyVersion".to_string())
+        );
+
+        let field: Field = syn::parse_quote! {
+            #[serde(rename(serialize = "rubyVersion"))]
+            ruby_version: String
+        };
+        assert_eq!(serde_rename(&field).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_all_container_unknown() {
+        let input = syn::parse_quote! {
+            #[cache_diff(rename = "IDK")]
+        };
+        let result = CacheDiffContainerAttributes::parse_all(&input);
         assert!(result.is_err(), "Expected an error, got {:?}", result);
         assert_eq!(
             format!("{}", result.err().unwrap()),
-            r#"Unknown cache_diff attribute: `unknown`. Must be one of `rename`, `display`, `ignore`"#
+            r#"Unknown cache_diff container attribute: `rename`. Must be one of `word_separator`, `align_names`, `use_serde_rename`, `compare_to`, `transition`, `custom`, `gate`, `removed`"#
         );
     }
 }
@@ -1,10 +1,21 @@
-use crate::attributes::CacheDiffAttributes;
-use proc_macro2::TokenStream;
-use quote::quote;
+use crate::attributes::{
+    line_placeholders, serde_rename, CacheDiffAttributes, CacheDiffContainerAttributes, OnChange,
+    Placeholder, Rename,
+};
+use proc_macro2::{Span, TokenStream};
+use quote::quote_spanned;
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
 use syn::Data::Struct;
-use syn::Fields::Named;
-use syn::{DataStruct, DeriveInput, Field, FieldsNamed, Ident, PathArguments};
+use syn::Fields::{Named, Unit, Unnamed};
+use syn::{DataStruct, DeriveInput, Field, FieldsNamed, FieldsUnnamed, Ident, PathArguments};
+
+/// Shown in place of both values for `#[cache_diff(sensitive)]` fields
+const REDACTED: &str = "[redacted]";
+
+/// Shown in place of `Some(_)` and `None` values for `#[cache_diff(presence)]` fields
+const SET: &str = "set";
+const UNSET: &str = "unset";
 
 /// Finalized state needed to construct a comparison
 ///
@@ -17,13 +28,40 @@ use syn::{DataStruct, DeriveInput, Field, FieldsNamed, Ident, PathArguments};
 /// ```
 struct CacheDiffField {
     field_identifier: Ident,
-    name: String,
-    display_fn: syn::Path,
+    name: Rename,
+    display_old_fn: syn::Path,
+    display_new_fn: syn::Path,
+    try_display: Option<syn::Path>,
+    on_change: OnChange,
+    generation: bool,
+    flatten: bool,
+    group: Option<String>,
+    weight: Option<i64>,
+    context: bool,
+    sensitive: bool,
+    line: Option<syn::LitStr>,
+    presence: bool,
+    informational: bool,
+    threshold: Option<syn::Lit>,
+    debug: bool,
+    sorted: bool,
+    unordered: bool,
+    assoc: bool,
+    round: Option<usize>,
+    truncate: Option<usize>,
+    compare_with: Option<syn::Path>,
+    added: bool,
+    cfgs: Vec<syn::Attribute>,
 }
 
 impl CacheDiffField {
-    fn new(field: &Field, attributes: CacheDiffAttributes) -> syn::Result<Option<Self>> {
-        if attributes.ignore.is_some() {
+    fn new(
+        field: &Field,
+        attributes: CacheDiffAttributes,
+        container: &CacheDiffContainerAttributes,
+    ) -> syn::Result<Option<Self>> {
+        // Markers have no value to show, they're ignored as if `cache_diff(ignore)`
+        if attributes.ignore.is_some() || is_marker(&field.ty) {
             Ok(None)
         } else {
             let field_identifier = field.ident.clone().ok_or_else(|| {
@@ -32,28 +70,103 @@ impl CacheDiffField {
                     "CacheDiff can only be used on structs with named fields",
                 )
             })?;
-            let name = attributes
-                .rename
-                .unwrap_or_else(|| field_identifier.to_string().replace("_", " "));
+            attributes
+                .check_compatible()
+                .map_err(|message| syn::Error::new(field.span(), message))?;
+            if attributes.sorted.is_some() && attributes.debug.is_none() {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "CacheDiff attribute `sorted` requires `debug`, use `cache_diff(debug, sorted)`",
+                ));
+            }
+            if attributes.added.is_some() && container.compare_to.is_none() {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "CacheDiff attribute `added` requires the container attribute `compare_to`",
+                ));
+            }
+            let generation = attributes.generation.is_some();
+            let flatten = attributes.flatten.is_some();
+            let context = attributes.context.is_some();
+            let sensitive = attributes.sensitive.is_some();
+            let presence = attributes.presence.is_some();
+            let debug = attributes.debug.is_some();
+            let sorted = attributes.sorted.is_some();
+            let unordered = attributes.unordered.is_some();
+            let assoc = attributes.assoc.is_some();
+            let word_separator = container.word_separator.as_deref().unwrap_or(" ");
+            let serde_rename = if container.use_serde_rename.is_some() {
+                serde_rename(field)?.map(Rename::Literal)
+            } else {
+                None
+            };
+            let name = attributes.rename.or(serde_rename).unwrap_or_else(|| {
+                Rename::Literal(
+                    field_identifier
+                        .unraw()
+                        .to_string()
+                        .replace("_", word_separator),
+                )
+            });
             let display_fn: syn::Path = attributes.display.unwrap_or_else(|| {
                 if is_pathbuf(&field.ty) {
-                    syn::parse_str("std::path::Path::display")
+                    syn::parse_str("::std::path::Path::display")
                         .expect("PathBuf::display parses as a syn::Path")
                 } else {
-                    syn::parse_str("std::convert::identity")
+                    syn::parse_str("::std::convert::identity")
                         .expect("std::convert::identity parses as a syn::Path")
                 }
             });
+            let display_old_fn = attributes.display_old.unwrap_or_else(|| display_fn.clone());
+            let display_new_fn = attributes.display_new.unwrap_or(display_fn);
+
+            let on_change = attributes.on_change.unwrap_or(OnChange::both);
 
             Ok(Some(CacheDiffField {
                 field_identifier,
                 name,
-                display_fn,
+                display_old_fn,
+                display_new_fn,
+                try_display: attributes.try_display,
+                on_change,
+                generation,
+                flatten,
+                group: attributes.group,
+                weight: attributes.weight,
+                context,
+                sensitive,
+                line: attributes.line,
+                presence,
+                informational: attributes.informational.is_some(),
+                threshold: attributes.threshold,
+                debug,
+                sorted,
+                unordered,
+                assoc,
+                round: attributes.round,
+                truncate: attributes.truncate,
+                compare_with: attributes.compare_with,
+                added: attributes.added.is_some(),
+                cfgs: field
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path().is_ident("cfg"))
+                    .cloned()
+                    .collect(),
             }))
         }
     }
 }
 
+/// Word shown between old and new values, unless `CacheDiffOptions` overrides it at runtime
+fn transition_argument(container: &CacheDiffContainerAttributes) -> TokenStream {
+    let span = Span::mixed_site();
+    let transition = container.transition.as_deref().unwrap_or("to");
+    quote_spanned! {span=>
+        transition = options.transition().unwrap_or(#transition)
+    }
+}
+
 fn is_pathbuf(ty: &syn::Type) -> bool {
     if let syn::Type::Path(type_path) = ty {
         if let Some(segment) = type_path.path.segments.last() {
@@ -63,57 +176,1066 @@ fn is_pathbuf(ty: &syn::Type) -> bool {
     false
 }
 
+/// `PhantomData<_>` or the unit type `()`
+fn is_marker(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData"),
+        syn::Type::Tuple(tuple) => tuple.elems.is_empty(),
+        _ => false,
+    }
+}
+
+/// Tokens for references to the old and new values of a field
+///
+/// A field that's already a reference is used as-is, `&old.field` would be a `&&T`
+fn borrow_values(field: &Field, field_ident: &Ident) -> (TokenStream, TokenStream) {
+    let span = Span::mixed_site();
+    if matches!(field.ty, syn::Type::Reference(_)) {
+        (
+            quote_spanned! {span=> old.#field_ident },
+            quote_spanned! {span=> self.#field_ident },
+        )
+    } else {
+        (
+            quote_spanned! {span=> &old.#field_ident },
+            quote_spanned! {span=> &self.#field_ident },
+        )
+    }
+}
+
+/// Reports each field named with `cache_diff(removed = ...)`, they only exist on the `compare_to` type
+fn removed_differences(container: &CacheDiffContainerAttributes) -> TokenStream {
+    let span = Span::mixed_site();
+    let word_separator = container.word_separator.as_deref().unwrap_or(" ");
+    let removed = container.removed.iter().map(|field_ident| {
+        let name = field_ident.unraw().to_string().replace("_", word_separator);
+        quote_spanned! {span=>
+            // Fails to compile when the `compare_to` type doesn't have the field
+            let _ = &old.#field_ident;
+            differences.push(::std::format!("{name} removed",
+                name = ::cache_diff::CacheDiff::fmt_value(self, &#name)
+            ));
+        }
+    });
+    quote_spanned! {span=> #(#removed)* }
+}
+
+/// Generates an implementation for a struct without fields (named, tuple, or unit), it never has differences
+fn create_empty_cache_diff(
+    struct_identifier: &Ident,
+    container: &CacheDiffContainerAttributes,
+) -> syn::Result<TokenStream> {
+    if let Some(custom) = &container.custom {
+        return Err(syn::Error::new_spanned(
+            custom,
+            "CacheDiff attribute `custom` requires a struct with at least one field",
+        ));
+    }
+    if let Some(gate) = &container.gate {
+        return Err(syn::Error::new(
+            gate.span(),
+            "CacheDiff attribute `gate` must name a field of the struct",
+        ));
+    }
+    let span = Span::mixed_site();
+    let diff_with = if let Some(other) = &container.compare_to {
+        if container.removed.is_empty() {
+            quote_spanned! {span=>
+                #[automatically_derived]
+                impl ::cache_diff::CacheDiffWith<#other> for #struct_identifier {
+                    fn diff_with(&self, _old: &#other) -> ::std::vec::Vec<::std::string::String> {
+                        ::std::vec::Vec::new()
+                    }
+                }
+            }
+        } else {
+            let removed = removed_differences(container);
+            quote_spanned! {span=>
+                #[automatically_derived]
+                impl ::cache_diff::CacheDiffWith<#other> for #struct_identifier {
+                    fn diff_with(&self, old: &#other) -> ::std::vec::Vec<::std::string::String> {
+                        let mut differences = ::std::vec::Vec::new();
+                        #removed
+                        differences
+                    }
+                }
+            }
+        }
+    } else {
+        quote_spanned! {span=>}
+    };
+
+    Ok(quote_spanned! {span=>
+        const _: () = {
+            #[automatically_derived]
+            impl ::cache_diff::CacheDiff for #struct_identifier {
+                fn diff(&self, _old: &Self) -> ::std::vec::Vec<::std::string::String> {
+                    ::std::vec::Vec::new()
+                }
+
+                fn changed_at_least(&self, _old: &Self, n: usize) -> bool {
+                    n == 0
+                }
+
+                fn changes_up_to(&self, _old: &Self, _limit: usize) -> usize {
+                    0
+                }
+
+                fn must_invalidate(&self, _old: &Self) -> bool {
+                    false
+                }
+
+                fn diff_partitioned(&self, _old: &Self) -> (::std::vec::Vec<::std::string::String>, ::std::vec::Vec<::std::string::String>) {
+                    (::std::vec::Vec::new(), ::std::vec::Vec::new())
+                }
+            }
+
+            #diff_with
+
+            impl #struct_identifier {
+                /// Names of the fields compared by `CacheDiff`, in the order their differences are reported
+                pub const CACHE_DIFF_FIELDS: &'static [&'static ::std::primitive::str] = &[];
+
+                /// Previous names given with `cache_diff(alias = "...")`, keyed by field identifier
+                pub const CACHE_DIFF_ALIASES: &'static [(&'static ::std::primitive::str, &'static [&'static ::std::primitive::str])] = &[];
+
+                /// Fields marked `cache_diff(ignore)` and the reason given, empty when there isn't one
+                pub const CACHE_DIFF_IGNORED: &'static [(&'static ::std::primitive::str, &'static ::std::primitive::str)] = &[];
+            }
+
+            ::cache_diff::__register_fields!(::std::stringify!(#struct_identifier), []);
+        };
+    })
+}
+
 pub fn create_cache_diff(item: TokenStream) -> syn::Result<TokenStream> {
-    let ast: DeriveInput = syn::parse2(item).unwrap();
-    let struct_identifier = ast.ident;
-    let fields = match ast.data {
+    let ast: DeriveInput = syn::parse2(item)?;
+    let struct_identifier = &ast.ident;
+    let container = CacheDiffContainerAttributes::from(&ast.attrs)?;
+    // A `custom` function only accepts `Self`, it can't be called with the `compare_to` type
+    if let (Some(custom), Some(_)) = (&container.custom, &container.compare_to) {
+        return Err(syn::Error::new_spanned(
+            custom,
+            "CacheDiff attribute `custom` cannot be combined with `compare_to`",
+        ));
+    }
+    if let (Some(removed), None) = (container.removed.first(), &container.compare_to) {
+        return Err(syn::Error::new(
+            removed.span(),
+            "CacheDiff attribute `removed` requires the container attribute `compare_to`",
+        ));
+    }
+    let fields = match &ast.data {
         Struct(DataStruct {
-            fields: Named(FieldsNamed { ref named, .. }),
+            fields: Named(FieldsNamed { named, .. }),
             ..
-        }) => named,
-        _ => unimplemented!("Only implemented for structs"),
+        }) if !named.is_empty() => named,
+        Struct(DataStruct {
+            fields: Named(_) | Unit,
+            ..
+        }) => return create_empty_cache_diff(struct_identifier, &container),
+        Struct(DataStruct {
+            fields: Unnamed(FieldsUnnamed { unnamed, .. }),
+            ..
+        }) if unnamed.is_empty() => return create_empty_cache_diff(struct_identifier, &container),
+        Struct(DataStruct {
+            fields: Unnamed(FieldsUnnamed { unnamed, .. }),
+            ..
+        }) => {
+            return Err(syn::Error::new_spanned(
+                unnamed,
+                "CacheDiff can only be used on structs with named fields",
+            ))
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast,
+                "CacheDiff can only be derived for structs with named or unnamed fields",
+            ))
+        }
     };
+    if let Some(gate) = container
+        .gate
+        .as_ref()
+        .filter(|&gate| !fields.iter().any(|f| f.ident.as_ref() == Some(gate)))
+    {
+        return Err(syn::Error::new(
+            gate.span(),
+            "CacheDiff attribute `gate` must name a field of the struct",
+        ));
+    }
+    // Generated code uses `Span::mixed_site()` so local bindings such as `old` and `differences`
+    // can't collide with user field names or `display` functions
+    let span = Span::mixed_site();
     let mut comparisons = Vec::new();
+    let mut with_comparisons = Vec::new();
+    let mut names = Vec::new();
+    let mut shown_names = Vec::new();
+    let mut compared_names = Vec::new();
+    let mut context_names = Vec::new();
+    let mut groups: Vec<(String, Vec<TokenStream>)> = Vec::new();
+    let mut has_groups = false;
+    let mut weighted = Vec::new();
+    let mut has_weights = false;
+    let mut contexts = Vec::new();
+    let mut counters = Vec::new();
+    let mut summaries = Vec::new();
+    let mut aliases = Vec::new();
+    let mut ignored = Vec::new();
+    let mut invalidations = Vec::new();
+    let mut partitioned = Vec::new();
     for f in fields.iter() {
         let attributes = CacheDiffAttributes::from(f)?;
-        let field = CacheDiffField::new(f, attributes)?;
+        // Recorded for every field, including ignored ones, so schemas can be reconciled
+        if let Some(ident) = f.ident.as_ref().filter(|_| !attributes.alias.is_empty()) {
+            let field_key = ident.unraw().to_string();
+            let field_aliases = &attributes.alias;
+            let cfgs = f.attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+            aliases.push(quote_spanned! {span=> #(#cfgs)* (#field_key, &[#(#field_aliases),*]) });
+        }
+        if let (Some(ident), Some(reason)) = (f.ident.as_ref(), &attributes.ignore) {
+            let field_key = ident.unraw().to_string();
+            let reason = reason.as_deref().unwrap_or_default();
+            let cfgs = f.attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+            ignored.push(quote_spanned! {span=> #(#cfgs)* (#field_key, #reason) });
+        }
+        let field = CacheDiffField::new(f, attributes, &container)?;
 
         if let Some(CacheDiffField {
             field_identifier: field_ident,
             name,
-            display_fn,
+            display_old_fn,
+            display_new_fn,
+            try_display,
+            on_change,
+            generation,
+            flatten,
+            group,
+            weight,
+            context,
+            sensitive,
+            line,
+            presence,
+            informational,
+            threshold,
+            debug,
+            sorted,
+            unordered,
+            assoc,
+            round,
+            truncate,
+            compare_with,
+            added,
+            cfgs,
         }) = field
         {
-            comparisons.push(quote! {
-                if self.#field_ident != old.#field_ident {
-                    differences.push(
-                        format!("{name} ({old} to {now})",
-                            name = #name,
-                            old = self.fmt_value(&#display_fn(&old.#field_ident)),
-                            now = self.fmt_value(&#display_fn(&self.#field_ident))
-                        )
+            // Sensitive values are never passed to `display` or `fmt_value`
+            let (old_value, now_value) = if sensitive {
+                (
+                    quote_spanned! {span=> #REDACTED },
+                    quote_spanned! {span=> #REDACTED },
+                )
+            } else if sorted {
+                (
+                    quote_spanned! {span=> ::cache_diff::__private::sorted_debug(&old.#field_ident) },
+                    quote_spanned! {span=> ::cache_diff::__private::sorted_debug(&self.#field_ident) },
+                )
+            } else if debug {
+                (
+                    quote_spanned! {span=> ::std::format!("{:?}", &old.#field_ident) },
+                    quote_spanned! {span=> ::std::format!("{:?}", &self.#field_ident) },
+                )
+            } else if let Some(round) = round {
+                (
+                    quote_spanned! {span=> ::std::format!("{:.*}", #round, &old.#field_ident) },
+                    quote_spanned! {span=> ::std::format!("{:.*}", #round, &self.#field_ident) },
+                )
+            } else if presence {
+                (
+                    quote_spanned! {span=> if old.#field_ident.is_some() { #SET } else { #UNSET } },
+                    quote_spanned! {span=> if self.#field_ident.is_some() { #SET } else { #UNSET } },
+                )
+            } else if let Some(try_display) = try_display {
+                let (old, now) = borrow_values(f, &field_ident);
+                (
+                    quote_spanned! {span=> ::cache_diff::__private::try_display(#try_display(#old)) },
+                    quote_spanned! {span=> ::cache_diff::__private::try_display(#try_display(#now)) },
+                )
+            } else {
+                let (old, now) = borrow_values(f, &field_ident);
+                (
+                    quote_spanned! {span=> #display_old_fn(#old) },
+                    quote_spanned! {span=> #display_new_fn(#now) },
+                )
+            };
+
+            // Truncation only changes what's shown, comparisons use the full values
+            let max = if let Some(max) = truncate {
+                quote_spanned! {span=>
+                    ::std::option::Option::Some(options.max_value_len().map_or(#max, |max| max.min(#max)))
+                }
+            } else {
+                quote_spanned! {span=> options.max_value_len() }
+            };
+            let (old_shown, now_shown) = (
+                quote_spanned! {span=> ::cache_diff::__private::Limited::new(&#old_value, #max) },
+                quote_spanned! {span=> ::cache_diff::__private::Limited::new(&#now_value, #max) },
+            );
+
+            // Generated code for the field only exists when the field does
+            let cfg = quote_spanned! {span=> #(#cfgs)* };
+            let has_cfg = !cfgs.is_empty();
+
+            if let Rename::Literal(value) = &name {
+                // Fields that are both behind `cfg`s may share a name, they're likely mutually exclusive
+                if !flatten
+                    && names.iter().any(|(other, other_has_cfg)| {
+                        other == &name && !(has_cfg && *other_has_cfg)
+                    })
+                {
+                    return Err(syn::Error::new_spanned(
+                        f,
+                        format!("Duplicate CacheDiff field name `{value}`, use `cache_diff(rename = \"...\")` to give each field a unique name"),
+                    ));
+                }
+            }
+
+            // Callers of `diff_with_names` may translate the name at runtime
+            let field_key = field_ident.unraw().to_string();
+            let shown_name = quote_spanned! {span=>
+                names.get(#field_key).copied().unwrap_or(#name)
+            };
+
+            if context {
+                contexts.push(quote_spanned! {span=>
+                    #cfg
+                    {
+                        differences.push(
+                            ::std::format!("{name}: {value}",
+                                name = #shown_name,
+                                value = ::cache_diff::CacheDiff::fmt_value(self, &#now_shown)
+                            )
+                        );
+                    }
+                });
+                context_names.push(quote_spanned! {span=> #cfg #name });
+                shown_names.push(quote_spanned! {span=> #cfg #shown_name });
+                names.push((name, has_cfg));
+                continue;
+            }
+
+            let (comparison, counter, invalidation, summary) = if flatten {
+                (
+                    quote_spanned! {span=>
+                        // Options and names can't be given at the same time, at least one is the default
+                        differences.extend(if names.is_empty() {
+                            ::cache_diff::CacheDiff::diff_with_options(&self.#field_ident, &old.#field_ident, options)
+                        } else {
+                            ::cache_diff::CacheDiff::diff_with_names(&self.#field_ident, &old.#field_ident, names)
+                        });
+                    },
+                    quote_spanned! {span=>
+                        // Only counts the remaining changes so the nested comparison can stop early too
+                        count += ::cache_diff::CacheDiff::changes_up_to(&self.#field_ident, &old.#field_ident, n - count);
+                        if count >= n {
+                            return count;
+                        }
+                    },
+                    quote_spanned! {span=>
+                        if ::cache_diff::CacheDiff::must_invalidate(&self.#field_ident, &old.#field_ident) {
+                            return true;
+                        }
+                    },
+                    // Inlined fields count individually
+                    quote_spanned! {span=>
+                        let (nested_changed, nested_total) = ::cache_diff::CacheDiff::diff_summary(&self.#field_ident, &old.#field_ident);
+                        changed += nested_changed;
+                        total += nested_total;
+                    },
+                )
+            } else if assoc {
+                // `display` applies to each value, keys must implement `Display`
+                let name_format = if container.align_names.is_some() {
+                    "{name:<name_width$}"
+                } else {
+                    "{name}"
+                };
+                let transition = transition_argument(&container);
+                let changed_line = syn::LitStr::new(
+                    &format!("{name_format} {{key}} ({{old}} {{transition}} {{now}})"),
+                    span,
+                );
+                let added_line =
+                    syn::LitStr::new(&format!("{name_format} {{key}} (added {{now}})"), span);
+                let removed_line =
+                    syn::LitStr::new(&format!("{name_format} {{key}} (removed {{old}})"), span);
+                let changes = quote_spanned! {span=>
+                    ::cache_diff::__private::assoc_changes(&old.#field_ident, &self.#field_ident)
+                };
+                let comparison = quote_spanned! {span=>
+                    for (key, old_value, now_value) in #changes {
+                        let key = ::cache_diff::CacheDiff::fmt_value(self, key);
+                        match (old_value, now_value) {
+                            (::std::option::Option::Some(old_value), ::std::option::Option::Some(now_value)) => {
+                                differences.push(::std::format!(#changed_line,
+                                    name = #shown_name,
+                                    key = key,
+                                    old = ::cache_diff::CacheDiff::fmt_old_value(self, &::cache_diff::__private::Limited::new(&#display_old_fn(old_value), options.max_value_len())),
+                                    now = ::cache_diff::CacheDiff::fmt_new_value(self, &::cache_diff::__private::Limited::new(&#display_new_fn(now_value), options.max_value_len())),
+                                    #transition
+                                ));
+                            }
+                            (::std::option::Option::None, ::std::option::Option::Some(now_value)) => {
+                                differences.push(::std::format!(#added_line,
+                                    name = #shown_name,
+                                    key = key,
+                                    now = ::cache_diff::CacheDiff::fmt_new_value(self, &::cache_diff::__private::Limited::new(&#display_new_fn(now_value), options.max_value_len()))
+                                ));
+                            }
+                            (::std::option::Option::Some(old_value), ::std::option::Option::None) => {
+                                differences.push(::std::format!(#removed_line,
+                                    name = #shown_name,
+                                    key = key,
+                                    old = ::cache_diff::CacheDiff::fmt_old_value(self, &::cache_diff::__private::Limited::new(&#display_old_fn(old_value), options.max_value_len()))
+                                ));
+                            }
+                            (::std::option::Option::None, ::std::option::Option::None) => {}
+                        }
+                    }
+                };
+                let counter = quote_spanned! {span=>
+                    count += #changes.len();
+                    if count >= n {
+                        return count;
+                    }
+                };
+                let invalidation = quote_spanned! {span=>
+                    if !#changes.is_empty() {
+                        return true;
+                    }
+                };
+                let summary = quote_spanned! {span=>
+                    total += 1;
+                    if !#changes.is_empty() {
+                        changed += 1;
+                    }
+                };
+                compared_names.push(quote_spanned! {span=> #cfg #name });
+                shown_names.push(quote_spanned! {span=> #cfg #shown_name });
+                names.push((name, has_cfg));
+                (comparison, counter, invalidation, summary)
+            } else if unordered {
+                // `display` applies to each element rather than the whole collection
+                let name_format = if container.align_names.is_some() {
+                    quote_spanned! {span=> "{name:<name_width$} ({changes})" }
+                } else {
+                    quote_spanned! {span=> "{name} ({changes})" }
+                };
+                let changes = quote_spanned! {span=>
+                    ::cache_diff::__private::unordered_changes(&old.#field_ident, &self.#field_ident)
+                };
+                let comparison = quote_spanned! {span=>
+                    if let ::std::option::Option::Some((added, removed)) = #changes {
+                        let mut changes = ::std::vec::Vec::new();
+                        if !added.is_empty() {
+                            changes.push(::std::format!("added {}",
+                                added
+                                    .into_iter()
+                                    .map(|value| ::cache_diff::CacheDiff::fmt_new_value(self, &::cache_diff::__private::Limited::new(&#display_new_fn(value), options.max_value_len())))
+                                    .collect::<::std::vec::Vec<::std::string::String>>()
+                                    .join(", ")
+                            ));
+                        }
+                        if !removed.is_empty() {
+                            changes.push(::std::format!("removed {}",
+                                removed
+                                    .into_iter()
+                                    .map(|value| ::cache_diff::CacheDiff::fmt_old_value(self, &::cache_diff::__private::Limited::new(&#display_old_fn(value), options.max_value_len())))
+                                    .collect::<::std::vec::Vec<::std::string::String>>()
+                                    .join(", ")
+                            ));
+                        }
+                        differences.push(
+                            ::std::format!(#name_format, name = #shown_name, changes = changes.join("; "))
+                        );
+                    }
+                };
+                let counter = quote_spanned! {span=>
+                    if #changes.is_some() {
+                        count += 1;
+                        if count >= n {
+                            return count;
+                        }
+                    }
+                };
+                let invalidation = quote_spanned! {span=>
+                    if #changes.is_some() {
+                        return true;
+                    }
+                };
+                let summary = quote_spanned! {span=>
+                    total += 1;
+                    if #changes.is_some() {
+                        changed += 1;
+                    }
+                };
+                compared_names.push(quote_spanned! {span=> #cfg #name });
+                shown_names.push(quote_spanned! {span=> #cfg #shown_name });
+                names.push((name, has_cfg));
+                (comparison, counter, invalidation, summary)
+            } else {
+                let changed = match on_change {
+                    // Replaces `PartialEq` entirely so the field's type doesn't need to implement it
+                    _ if compare_with.is_some() => {
+                        let (old, now) = borrow_values(f, &field_ident);
+                        quote_spanned! {span=> !#compare_with(#old, #now) }
+                    }
+                    // Compared by the same normalized strings that are displayed
+                    _ if sorted || round.is_some() => {
+                        quote_spanned! {span=> #now_value != #old_value }
+                    }
+                    _ if threshold.is_some() => {
+                        quote_spanned! {span=> (self.#field_ident >= #threshold) != (old.#field_ident >= #threshold) }
+                    }
+                    _ if presence => {
+                        quote_spanned! {span=> self.#field_ident.is_some() != old.#field_ident.is_some() }
+                    }
+                    _ if generation => {
+                        quote_spanned! {span=> self.#field_ident > old.#field_ident }
+                    }
+                    OnChange::enable => {
+                        quote_spanned! {span=> !old.#field_ident && self.#field_ident }
+                    }
+                    OnChange::disable => {
+                        quote_spanned! {span=> old.#field_ident && !self.#field_ident }
+                    }
+                    OnChange::both => {
+                        quote_spanned! {span=> self.#field_ident != old.#field_ident }
+                    }
+                };
+                // Normalized strings are compared, compute them once and show the same ones
+                let (normalized, shown_changed, old_shown, now_shown) = if sorted || round.is_some()
+                {
+                    (
+                        quote_spanned! {span=>
+                            let old_value = #old_value;
+                            let now_value = #now_value;
+                        },
+                        quote_spanned! {span=> now_value != old_value },
+                        quote_spanned! {span=> ::cache_diff::__private::Limited::new(&old_value, #max) },
+                        quote_spanned! {span=> ::cache_diff::__private::Limited::new(&now_value, #max) },
+                    )
+                } else {
+                    (
+                        quote_spanned! {span=>},
+                        changed.clone(),
+                        old_shown,
+                        now_shown,
+                    )
+                };
+                // A custom `line` may omit placeholders, `format!` rejects unused named arguments,
+                // values are only rendered for the placeholders that are shown
+                let (line, placeholders, transition) = if let Some(line) = line {
+                    let placeholders = line_placeholders(&line.value())
+                        .map_err(|message| syn::Error::new(line.span(), message))?;
+                    (quote_spanned! {span=> #line }, placeholders, None)
+                } else {
+                    let name_format = if container.align_names.is_some() {
+                        "{name:<name_width$}"
+                    } else {
+                        "{name}"
+                    };
+                    // Spanned so `name_width$` resolves to the binding from the generated prelude
+                    let line = syn::LitStr::new(
+                        &format!("{name_format} ({{old}} {{transition}} {{now}})"),
+                        span,
                     );
+                    (
+                        quote_spanned! {span=> #line },
+                        vec![Placeholder::name, Placeholder::old, Placeholder::now],
+                        Some(transition_argument(&container)),
+                    )
+                };
+                let arguments = placeholders
+                    .iter()
+                    .map(|placeholder| match placeholder {
+                        Placeholder::name => quote_spanned! {span=> name = #shown_name },
+                        Placeholder::old => quote_spanned! {span=>
+                            old = ::cache_diff::CacheDiff::fmt_old_value(self, &#old_shown)
+                        },
+                        Placeholder::now => quote_spanned! {span=>
+                            now = ::cache_diff::CacheDiff::fmt_new_value(self, &#now_shown)
+                        },
+                    })
+                    .chain(transition);
+                let comparison = quote_spanned! {span=>
+                    #normalized
+                    if #shown_changed {
+                        differences.push(
+                            ::std::format!(#line, #(#arguments),*)
+                        );
+                    }
+                };
+                let counter = quote_spanned! {span=>
+                    if #changed {
+                        count += 1;
+                        if count >= n {
+                            return count;
+                        }
+                    }
+                };
+                let invalidation = quote_spanned! {span=>
+                    if #changed {
+                        return true;
+                    }
+                };
+                let summary = quote_spanned! {span=>
+                    total += 1;
+                    if #changed {
+                        changed += 1;
+                    }
+                };
+                compared_names.push(quote_spanned! {span=> #cfg #name });
+                shown_names.push(quote_spanned! {span=> #cfg #shown_name });
+                names.push((name, has_cfg));
+                (comparison, counter, invalidation, summary)
+            };
+
+            // The `compare_to` type doesn't have the field, there's no old value to compare
+            let with_comparison = if added {
+                quote_spanned! {span=>
+                    differences.push(::std::format!("{name} added",
+                        name = ::cache_diff::CacheDiff::fmt_value(self, &#shown_name)
+                    ));
+                }
+            } else {
+                comparison.clone()
+            };
+            let (comparison, with_comparison) = if informational {
+                (
+                    quote_spanned! {span=>
+                        if options.include_informational() {
+                            #comparison
+                        }
+                    },
+                    quote_spanned! {span=>
+                        if options.include_informational() {
+                            #with_comparison
+                        }
+                    },
+                )
+            } else {
+                (comparison, with_comparison)
+            };
+            let comparison = quote_spanned! {span=> #cfg { #comparison } };
+            with_comparisons.push(quote_spanned! {span=> #cfg { #with_comparison } });
+            let counter = quote_spanned! {span=> #cfg { #counter } };
+            let invalidation = quote_spanned! {span=> #cfg { #invalidation } };
+            summaries.push(quote_spanned! {span=> #cfg { #summary } });
+
+            if !informational {
+                invalidations.push(invalidation);
+            }
+            partitioned.push(if informational {
+                quote_spanned! {span=>
+                    {
+                        let mut differences = ::std::vec::Vec::new();
+                        #comparison
+                        informational.extend(differences);
+                    }
+                }
+            } else if flatten {
+                quote_spanned! {span=>
+                    #cfg
+                    {
+                        let (nested_invalidating, nested_informational) = ::cache_diff::CacheDiff::diff_partitioned(&self.#field_ident, &old.#field_ident);
+                        invalidating.extend(nested_invalidating);
+                        informational.extend(nested_informational);
+                    }
+                }
+            } else {
+                quote_spanned! {span=>
+                    {
+                        let mut differences = ::std::vec::Vec::new();
+                        #comparison
+                        invalidating.extend(differences);
+                    }
                 }
             });
+
+            has_groups |= group.is_some();
+            let group = group.unwrap_or_default();
+            if let Some((_, grouped)) = groups.iter_mut().find(|(name, _)| name == &group) {
+                grouped.push(comparison.clone());
+            } else {
+                groups.push((group, vec![comparison.clone()]));
+            }
+            has_weights |= weight.is_some();
+            let weight = weight.unwrap_or_default();
+            weighted.push(quote_spanned! {span=>
+                {
+                    let mut differences = ::std::vec::Vec::new();
+                    #comparison
+                    weighted.extend(differences.into_iter().map(|difference| (#weight, difference)));
+                }
+            });
+            comparisons.push(comparison);
+            counters.push(counter);
         }
     }
 
-    if comparisons.is_empty() {
+    // A `custom` hook can compare fields that are individually ignored
+    if comparisons.is_empty() && container.custom.is_none() {
         Err(syn::Error::new(
             struct_identifier.span(),
             "No fields to compare for CacheDiff, ensure struct has at least one named field that isn't `cache_diff(ignore)`-d",
         ))
     } else {
-        Ok(quote! {
-            #[allow(clippy::useless_attribute)]
-            use cache_diff as _cache_diff;
-            impl _cache_diff::CacheDiff for #struct_identifier {
-                fn diff(&self, old: &Self) -> Vec<String> {
-                    let mut differences = Vec::new();
-                    #(#comparisons)*
+        // Context fields are reported last, flattened fields are listed on their own type
+        let output_names = compared_names.iter().chain(&context_names);
+        let registered_names = output_names.clone();
+        // Width is computed at runtime since a `rename` may point to a `const`
+        let width_prelude = if container.align_names.is_some() {
+            quote_spanned! {span=>
+                let name_width = [#(#shown_names),*]
+                    .iter()
+                    .map(|name: &&str| name.chars().count())
+                    .max()
+                    .unwrap_or_default();
+            }
+        } else {
+            quote_spanned! {span=>}
+        };
+        let default_names = quote_spanned! {span=>
+            let names: &::std::collections::BTreeMap<&::std::primitive::str, &::std::primitive::str> = &::std::collections::BTreeMap::new();
+        };
+        let default_options = quote_spanned! {span=>
+            let options = &<::cache_diff::CacheDiffOptions as ::std::default::Default>::default();
+        };
+        // Methods without `names` or `options` arguments use the compile time names and default options
+        let prelude = quote_spanned! {span=>
+            #default_names
+            #default_options
+            #width_prelude
+        };
+        let context = if contexts.is_empty() {
+            quote_spanned! {span=>}
+        } else {
+            quote_spanned! {span=>
+                if !differences.is_empty() {
+                    #(#contexts)*
+                }
+            }
+        };
+        // Context is shown with the invalidating differences when there are any
+        let partitioned_context = if contexts.is_empty() {
+            quote_spanned! {span=>}
+        } else {
+            quote_spanned! {span=>
+                {
+                    let differences = if invalidating.is_empty() {
+                        &mut informational
+                    } else {
+                        &mut invalidating
+                    };
+                    #context
+                }
+            }
+        };
+        // Differences from a `custom` hook come after every field's differences, context is still shown last
+        let (
+            custom,
+            custom_grouped,
+            custom_weighted,
+            custom_counter,
+            custom_invalidation,
+            custom_partitioned,
+        ) = if let Some(custom) = &container.custom {
+            (
+                quote_spanned! {span=>
+                    differences.extend(#custom(self, old));
+                },
+                quote_spanned! {span=>
+                    let custom = #custom(self, old);
+                    if !custom.is_empty() {
+                        if let ::std::option::Option::Some((_, differences)) = groups.iter_mut().find(|(group, _): &&mut (::std::string::String, _)| group.is_empty()) {
+                            differences.extend(custom);
+                        } else {
+                            groups.push((::std::string::String::new(), custom));
+                        }
+                    }
+                },
+                quote_spanned! {span=>
+                    weighted.extend(#custom(self, old).into_iter().map(|difference| (0, difference)));
+                },
+                quote_spanned! {span=>
+                    count += #custom(self, old).len();
+                    if count >= n {
+                        return count;
+                    }
+                },
+                quote_spanned! {span=>
+                    if !#custom(self, old).is_empty() {
+                        return true;
+                    }
+                },
+                quote_spanned! {span=>
+                    invalidating.extend(#custom(self, old));
+                },
+            )
+        } else {
+            (
+                quote_spanned! {span=>},
+                quote_spanned! {span=>},
+                quote_spanned! {span=>},
+                quote_spanned! {span=>},
+                quote_spanned! {span=>},
+                quote_spanned! {span=>},
+            )
+        };
+        // A changed `gate` field makes the other differences meaningless, it's checked before any of them
+        let gate_transition = transition_argument(&container);
+        let gate = |returned: TokenStream| {
+            if let Some(gate) = &container.gate {
+                quote_spanned! {span=>
+                    if old.#gate != self.#gate {
+                        let message = ::std::format!(
+                            "cache schema changed ({old} {transition} {now})",
+                            old = ::cache_diff::CacheDiff::fmt_old_value(self, &old.#gate),
+                            now = ::cache_diff::CacheDiff::fmt_new_value(self, &self.#gate),
+                            #gate_transition
+                        );
+                        #returned
+                    }
+                }
+            } else {
+                quote_spanned! {span=>}
+            }
+        };
+        let gate_changed = |returned: TokenStream| {
+            if let Some(gate) = &container.gate {
+                quote_spanned! {span=>
+                    if old.#gate != self.#gate {
+                        return #returned;
+                    }
+                }
+            } else {
+                quote_spanned! {span=>}
+            }
+        };
+        let gate_diff = gate(quote_spanned! {span=>
+            return ::std::vec![message];
+        });
+        let gate_write = gate(quote_spanned! {span=>
+            differences.push(message);
+            return differences.finish();
+        });
+        let gate_grouped = gate(quote_spanned! {span=>
+            return ::std::vec![(::std::string::String::new(), ::std::vec![message])];
+        });
+        let gate_partitioned = gate(quote_spanned! {span=>
+            return (::std::vec![message], ::std::vec::Vec::new());
+        });
+        let gate_counter = gate_changed(quote_spanned! {span=> 1 });
+        let gate_invalidation = gate_changed(quote_spanned! {span=> true });
+        // Only the gate field is reported as changed, out of every compared field
+        let gate_summary = gate_changed(quote_spanned! {span=> (1, total) });
+
+        // Context is shown under the default group whenever any group has differences
+        let grouped_context = if contexts.is_empty() {
+            quote_spanned! {span=>}
+        } else {
+            quote_spanned! {span=>
+                if !groups.is_empty() {
+                    let mut differences = ::std::vec::Vec::new();
+                    #(#contexts)*
+                    if let ::std::option::Option::Some((_, grouped)) = groups.iter_mut().find(|(group, _): &&mut (::std::string::String, _)| group.is_empty()) {
+                        grouped.extend(differences);
+                    } else {
+                        groups.push((::std::string::String::new(), differences));
+                    }
+                }
+            }
+        };
+        let grouped_diff = if has_groups {
+            let groups = groups.iter().map(|(group, comparisons)| {
+                quote_spanned! {span=>
+                    {
+                        let mut differences = ::std::vec::Vec::new();
+                        #(#comparisons)*
+                        if !differences.is_empty() {
+                            groups.push((::std::string::String::from(#group), differences));
+                        }
+                    }
+                }
+            });
+            quote_spanned! {span=>
+                fn grouped_diff(&self, old: &Self) -> ::std::vec::Vec<(::std::string::String, ::std::vec::Vec<::std::string::String>)> {
+                    #prelude
+                    #gate_grouped
+                    let mut groups = ::std::vec::Vec::new();
+                    #(#groups)*
+                    #custom_grouped
+                    #grouped_context
+                    groups
+                }
+            }
+        } else {
+            quote_spanned! {span=>}
+        };
+
+        let diff_weighted = if has_weights {
+            quote_spanned! {span=>
+                fn diff_weighted(&self, old: &Self) -> ::std::vec::Vec<::std::string::String> {
+                    #prelude
+                    #gate_diff
+                    let mut weighted: ::std::vec::Vec<(::std::primitive::i64, ::std::string::String)> = ::std::vec::Vec::new();
+                    #(#weighted)*
+                    #custom_weighted
+                    weighted.sort_by(|(a, _), (b, _)| b.cmp(a));
+                    let mut differences: ::std::vec::Vec<::std::string::String> = weighted.into_iter().map(|(_, difference)| difference).collect();
+                    #context
                     differences
                 }
             }
+        } else {
+            quote_spanned! {span=>}
+        };
+
+        let diff_with = if let Some(other) = &container.compare_to {
+            let removed = removed_differences(&container);
+            quote_spanned! {span=>
+                #[automatically_derived]
+                impl ::cache_diff::CacheDiffWith<#other> for #struct_identifier {
+                    fn diff_with(&self, old: &#other) -> ::std::vec::Vec<::std::string::String> {
+                        #prelude
+                        #gate_diff
+                        let mut differences = ::std::vec::Vec::new();
+                        #(#with_comparisons)*
+                        #removed
+                        #context
+                        differences
+                    }
+                }
+            }
+        } else {
+            quote_spanned! {span=>}
+        };
+
+        // Wrapped in an anonymous const so nothing generated can leak into the caller's module
+        Ok(quote_spanned! {span=>
+            const _: () = {
+                #[automatically_derived]
+                impl ::cache_diff::CacheDiff for #struct_identifier {
+                    fn diff(&self, old: &Self) -> ::std::vec::Vec<::std::string::String> {
+                        ::cache_diff::CacheDiff::diff_with_options(self, old, &<::cache_diff::CacheDiffOptions as ::std::default::Default>::default())
+                    }
+
+                    fn diff_with_options(
+                        &self,
+                        old: &Self,
+                        options: &::cache_diff::CacheDiffOptions,
+                    ) -> ::std::vec::Vec<::std::string::String> {
+                        #default_names
+                        #width_prelude
+                        #gate_diff
+                        let mut differences = ::std::vec::Vec::new();
+                        #(#comparisons)*
+                        #custom
+                        #context
+                        differences
+                    }
+
+                    fn diff_with_names(
+                        &self,
+                        old: &Self,
+                        names: &::std::collections::BTreeMap<&::std::primitive::str, &::std::primitive::str>,
+                    ) -> ::std::vec::Vec<::std::string::String> {
+                        #default_options
+                        #width_prelude
+                        #gate_diff
+                        let mut differences = ::std::vec::Vec::new();
+                        #(#comparisons)*
+                        #custom
+                        #context
+                        differences
+                    }
+
+                    fn write_diff_to<W: ::std::io::Write>(&self, old: &Self, writer: &mut W) -> ::std::io::Result<()> {
+                        #prelude
+                        let mut differences = ::cache_diff::__private::WriteSink::new(writer);
+                        #gate_write
+                        #(#comparisons)*
+                        #custom
+                        #context
+                        differences.finish()
+                    }
+
+                    fn changed_at_least(&self, old: &Self, n: usize) -> bool {
+                        ::cache_diff::CacheDiff::changes_up_to(self, old, n) >= n
+                    }
+
+                    fn changes_up_to(&self, old: &Self, n: usize) -> usize {
+                        let mut count = 0;
+                        if count >= n {
+                            return count;
+                        }
+                        #gate_counter
+                        #(#counters)*
+                        #custom_counter
+                        count
+                    }
+
+                    fn diff_summary(&self, old: &Self) -> (::std::primitive::usize, ::std::primitive::usize) {
+                        let mut changed = 0;
+                        let mut total = 0;
+                        #(#summaries)*
+                        #gate_summary
+                        (changed, total)
+                    }
+
+                    fn must_invalidate(&self, old: &Self) -> bool {
+                        #gate_invalidation
+                        #(#invalidations)*
+                        #custom_invalidation
+                        false
+                    }
+
+                    fn diff_partitioned(&self, old: &Self) -> (::std::vec::Vec<::std::string::String>, ::std::vec::Vec<::std::string::String>) {
+                        #prelude
+                        let mut invalidating = ::std::vec::Vec::new();
+                        let mut informational = ::std::vec::Vec::new();
+                        #gate_partitioned
+                        #(#partitioned)*
+                        #custom_partitioned
+                        #partitioned_context
+                        (invalidating, informational)
+                    }
+
+                    #grouped_diff
+
+                    #diff_weighted
+                }
+
+                #diff_with
+
+                impl #struct_identifier {
+                    /// Names of the fields compared by `CacheDiff`, in the order their differences are reported
+                    pub const CACHE_DIFF_FIELDS: &'static [&'static ::std::primitive::str] = &[#(#output_names),*];
+
+                    /// Previous names given with `cache_diff(alias = "...")`, keyed by field identifier
+                    pub const CACHE_DIFF_ALIASES: &'static [(&'static ::std::primitive::str, &'static [&'static ::std::primitive::str])] = &[#(#aliases),*];
+
+                    /// Fields marked `cache_diff(ignore)` and the reason given, empty when there isn't one
+                    pub const CACHE_DIFF_IGNORED: &'static [(&'static ::std::primitive::str, &'static ::std::primitive::str)] = &[#(#ignored),*];
+                }
+
+                ::cache_diff::__register_fields!(::std::stringify!(#struct_identifier), [#(#registered_names),*]);
+            };
         })
     }
 }
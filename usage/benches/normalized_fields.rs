@@ -0,0 +1,71 @@
+//! Times `diff` for fields that are normalized before they're compared and displayed
+//!
+//! A `round` or `debug, sorted` value is computed once per difference, a `display` function is only
+//! called for the values a `line` shows.
+
+use cache_diff::CacheDiff;
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 2_000;
+
+#[derive(CacheDiff)]
+struct Normalized {
+    #[cache_diff(round = 2)]
+    ratio: f64,
+    #[cache_diff(debug, sorted)]
+    gems: HashSet<String>,
+}
+
+#[derive(CacheDiff)]
+struct SlowDisplay {
+    #[cache_diff(display = slow, line = "{name} is now {now}")]
+    version: String,
+}
+
+// Deliberately expensive
+fn slow(value: &str) -> String {
+    (0..200).fold(String::new(), |mut rendered, _| {
+        rendered.clear();
+        rendered.push_str(value);
+        black_box(rendered)
+    })
+}
+
+fn normalized(version: &str, ratio: f64) -> Normalized {
+    Normalized {
+        ratio,
+        gems: (0..500).map(|i| format!("gem-{i}-{version}")).collect(),
+    }
+}
+
+fn bench<T: CacheDiff>(name: &str, now: &T, old: &T) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(now.diff(black_box(old)));
+    }
+    println!("{name:<24} {:>12?}/iter", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    bench(
+        "normalized changed",
+        &normalized("2", 0.5),
+        &normalized("1", 0.25),
+    );
+    bench(
+        "normalized unchanged",
+        &normalized("1", 0.5),
+        &normalized("1", 0.5),
+    );
+    bench(
+        "slow display changed",
+        &SlowDisplay {
+            version: "3.4.0".to_string(),
+        },
+        &SlowDisplay {
+            version: "3.3.0".to_string(),
+        },
+    );
+}
@@ -113,6 +113,2245 @@ mod tests {
         );
     }
 
+    #[test]
+    fn on_change_bool_field() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(on_change = "enable")]
+            enabled: bool,
+            #[cache_diff(on_change = "disable")]
+            disabled: bool,
+            #[cache_diff(on_change = "both")]
+            toggled: bool,
+        }
+        let diff = Metadata {
+            enabled: true,
+            disabled: true,
+            toggled: true,
+        }
+        .diff(&Metadata {
+            enabled: false,
+            disabled: false,
+            toggled: false,
+        });
+        assert_eq!(
+            diff,
+            vec![
+                "enabled (`false` to `true`)".to_string(),
+                "toggled (`false` to `true`)".to_string()
+            ]
+        );
+
+        let diff = Metadata {
+            enabled: false,
+            disabled: false,
+            toggled: false,
+        }
+        .diff(&Metadata {
+            enabled: true,
+            disabled: true,
+            toggled: true,
+        });
+        assert_eq!(
+            diff,
+            vec![
+                "disabled (`true` to `false`)".to_string(),
+                "toggled (`true` to `false`)".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn grouped_fields() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(group = "Toolchain")]
+            ruby_version: String,
+            #[cache_diff(group = "Operating System")]
+            distro: String,
+            #[cache_diff(group = "Toolchain")]
+            bundler_version: String,
+            #[cache_diff(group = "Operating System")]
+            arch: String,
+            other: String,
+        }
+        let metadata = Metadata {
+            ruby_version: "3.4.0".to_string(),
+            distro: "Ubuntu".to_string(),
+            bundler_version: "2.6.0".to_string(),
+            arch: "arm64".to_string(),
+            other: "same".to_string(),
+        };
+        let diff = metadata.grouped_diff(&Metadata {
+            ruby_version: "3.3.0".to_string(),
+            distro: "Ubuntu".to_string(),
+            bundler_version: "2.5.0".to_string(),
+            arch: "amd64".to_string(),
+            other: "different".to_string(),
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                (
+                    "Toolchain".to_string(),
+                    vec![
+                        "ruby version (`3.3.0` to `3.4.0`)".to_string(),
+                        "bundler version (`2.5.0` to `2.6.0`)".to_string()
+                    ]
+                ),
+                (
+                    "Operating System".to_string(),
+                    vec!["arch (`amd64` to `arm64`)".to_string()]
+                ),
+                (
+                    "".to_string(),
+                    vec!["other (`different` to `same`)".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_from_const_path() {
+        mod labels {
+            pub(crate) const RUBY_VERSION: &str = "Ruby version";
+        }
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(rename = labels::RUBY_VERSION)]
+            version: String,
+        }
+        let metadata = Metadata {
+            version: "3.4.0".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            version: "3.3.0".to_string(),
+        });
+
+        assert_eq!(diff, vec!["Ruby version (`3.3.0` to `3.4.0`)".to_string()]);
+    }
+
+    #[test]
+    fn word_separator_container() {
+        #[derive(CacheDiff)]
+        #[cache_diff(word_separator = " :: ")]
+        struct Metadata {
+            ruby_version: String,
+            #[cache_diff(rename = "bundler_version")]
+            bundler_version: String,
+        }
+        let metadata = Metadata {
+            ruby_version: "3.4.0".to_string(),
+            bundler_version: "2.6.0".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            ruby_version: "3.3.0".to_string(),
+            bundler_version: "2.5.0".to_string(),
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "ruby :: version (`3.3.0` to `3.4.0`)".to_string(),
+                "bundler_version (`2.5.0` to `2.6.0`)".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn align_names_container() {
+        #[derive(CacheDiff)]
+        #[cache_diff(align_names)]
+        struct Metadata {
+            #[cache_diff(rename = "Ruby version")]
+            version: String,
+            distro: String,
+            #[cache_diff(ignore)]
+            _a_very_long_ignored_name: String,
+        }
+        let metadata = Metadata {
+            version: "3.4.0".to_string(),
+            distro: "Ubuntu".to_string(),
+            _a_very_long_ignored_name: "".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            version: "3.3.0".to_string(),
+            distro: "Alpine".to_string(),
+            _a_very_long_ignored_name: "".to_string(),
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "Ruby version (`3.3.0` to `3.4.0`)".to_string(),
+                "distro       (`Alpine` to `Ubuntu`)".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn generation_field() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(generation)]
+            build_generation: u32,
+        }
+        let metadata = Metadata {
+            build_generation: 2,
+        };
+
+        assert_eq!(
+            metadata.diff(&Metadata {
+                build_generation: 1
+            }),
+            vec!["build generation (`1` to `2`)".to_string()]
+        );
+        assert!(metadata
+            .diff(&Metadata {
+                build_generation: 2
+            })
+            .is_empty());
+        assert!(metadata
+            .diff(&Metadata {
+                build_generation: 3
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn report_multiple_structs() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            version: String,
+            arch: String,
+        }
+        let metadata = |version: &str, arch: &str| Metadata {
+            version: version.to_string(),
+            arch: arch.to_string(),
+        };
+        let mut report = cache_diff::Report::default();
+        assert_eq!(report.render(), None);
+
+        report
+            .add(
+                "Ruby",
+                &metadata("3.4.0", "arm64"),
+                &metadata("3.4.0", "arm64"),
+            )
+            .add(
+                "Node",
+                &metadata("22.0.0", "arm64"),
+                &metadata("20.0.0", "amd64"),
+            );
+        assert_eq!(
+            report.render(),
+            Some(
+                "Node:\n- version (`20.0.0` to `22.0.0`)\n- arch (`amd64` to `arm64`)".to_string()
+            )
+        );
+
+        report.add(
+            "Bundler",
+            &metadata("2.6.0", "arm64"),
+            &metadata("2.5.0", "arm64"),
+        );
+        assert_eq!(
+            report.render(),
+            Some(
+                "Node:\n- version (`20.0.0` to `22.0.0`)\n- arch (`amd64` to `arm64`)\n\nBundler:\n- version (`2.5.0` to `2.6.0`)"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn raw_identifier_field() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            r#type: String,
+        }
+        let metadata = Metadata {
+            r#type: "release".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            r#type: "debug".to_string(),
+        });
+
+        assert_eq!(diff, vec!["type (`debug` to `release`)".to_string()]);
+    }
+
+    #[test]
+    fn flatten_field() {
+        #[derive(CacheDiff)]
+        struct Common {
+            distro: String,
+        }
+
+        // Manually implemented so `fmt_value` differs from the parent's
+        struct Toolchain {
+            version: String,
+        }
+        impl CacheDiff for Toolchain {
+            fn diff(&self, old: &Self) -> Vec<String> {
+                let mut differences = Vec::new();
+                if self.version != old.version {
+                    differences.push(format!(
+                        "toolchain {} to {}",
+                        self.fmt_value(&old.version),
+                        self.fmt_value(&self.version)
+                    ));
+                }
+                differences
+            }
+
+            fn fmt_value<T: std::fmt::Display>(&self, value: &T) -> String {
+                format!("<{value}>")
+            }
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            version: String,
+            #[cache_diff(flatten)]
+            common: Common,
+            #[cache_diff(flatten)]
+            toolchain: Toolchain,
+        }
+        let metadata = Metadata {
+            version: "3.4.0".to_string(),
+            common: Common {
+                distro: "Ubuntu".to_string(),
+            },
+            toolchain: Toolchain {
+                version: "1.84".to_string(),
+            },
+        };
+        let diff = metadata.diff(&Metadata {
+            version: "3.3.0".to_string(),
+            common: Common {
+                distro: "Alpine".to_string(),
+            },
+            toolchain: Toolchain {
+                version: "1.83".to_string(),
+            },
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "version (`3.3.0` to `3.4.0`)".to_string(),
+                "distro (`Alpine` to `Ubuntu`)".to_string(),
+                "toolchain <1.83> to <1.84>".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn use_serde_rename_container() {
+        #[derive(CacheDiff, serde::Serialize)]
+        #[cache_diff(use_serde_rename)]
+        struct Metadata {
+            #[serde(rename = "Ruby version")]
+            version: String,
+            #[serde(rename = "os")]
+            #[cache_diff(rename = "Distro")]
+            distro: String,
+            arch: String,
+        }
+        let metadata = Metadata {
+            version: "3.4.0".to_string(),
+            distro: "Ubuntu".to_string(),
+            arch: "arm64".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            version: "3.3.0".to_string(),
+            distro: "Alpine".to_string(),
+            arch: "amd64".to_string(),
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "Ruby version (`3.3.0` to `3.4.0`)".to_string(),
+                "Distro (`Alpine` to `Ubuntu`)".to_string(),
+                "arch (`amd64` to `arm64`)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn weighted_fields() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(weight = -1)]
+            build_id: String,
+            version: String,
+            #[cache_diff(weight = 5)]
+            distro: String,
+            arch: String,
+        }
+        let metadata = Metadata {
+            build_id: "b".to_string(),
+            version: "3.4.0".to_string(),
+            distro: "Ubuntu".to_string(),
+            arch: "arm64".to_string(),
+        };
+        let old = Metadata {
+            build_id: "a".to_string(),
+            version: "3.3.0".to_string(),
+            distro: "Alpine".to_string(),
+            arch: "amd64".to_string(),
+        };
+
+        assert_eq!(
+            metadata.diff_weighted(&old),
+            vec![
+                "distro (`Alpine` to `Ubuntu`)".to_string(),
+                "version (`3.3.0` to `3.4.0`)".to_string(),
+                "arch (`amd64` to `arm64`)".to_string(),
+                "build id (`a` to `b`)".to_string(),
+            ]
+        );
+        assert_eq!(
+            metadata.diff(&old),
+            vec![
+                "build id (`a` to `b`)".to_string(),
+                "version (`3.3.0` to `3.4.0`)".to_string(),
+                "distro (`Alpine` to `Ubuntu`)".to_string(),
+                "arch (`amd64` to `arm64`)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn hygiene_internal_bindings() {
+        fn old(value: &String) -> String {
+            format!("display {value}")
+        }
+        #[derive(CacheDiff)]
+        #[cache_diff(align_names)]
+        struct Metadata {
+            differences: String,
+            #[cache_diff(display = old)]
+            old: String,
+            name_width: String,
+        }
+        let metadata = Metadata {
+            differences: "b".to_string(),
+            old: "b".to_string(),
+            name_width: "b".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            differences: "a".to_string(),
+            old: "a".to_string(),
+            name_width: "a".to_string(),
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "differences (`a` to `b`)".to_string(),
+                "old         (`display a` to `display b`)".to_string(),
+                "name width  (`a` to `b`)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_to_other_type() {
+        use cache_diff::CacheDiffWith;
+
+        struct MetadataV1 {
+            ruby_version: String,
+            distro: String,
+        }
+
+        #[derive(CacheDiff)]
+        #[cache_diff(compare_to = MetadataV1)]
+        struct Metadata {
+            ruby_version: String,
+            #[cache_diff(rename = "OS")]
+            distro: String,
+            #[cache_diff(ignore)]
+            _arch: String,
+        }
+        let metadata = Metadata {
+            ruby_version: "3.4.0".to_string(),
+            distro: "Ubuntu".to_string(),
+            _arch: "arm64".to_string(),
+        };
+
+        assert_eq!(
+            metadata.diff_with(&MetadataV1 {
+                ruby_version: "3.3.0".to_string(),
+                distro: "Alpine".to_string(),
+            }),
+            vec![
+                "ruby version (`3.3.0` to `3.4.0`)".to_string(),
+                "OS (`Alpine` to `Ubuntu`)".to_string(),
+            ]
+        );
+        assert!(metadata
+            .diff_with(&MetadataV1 {
+                ruby_version: "3.4.0".to_string(),
+                distro: "Ubuntu".to_string(),
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn compare_to_added_field() {
+        use cache_diff::CacheDiffWith;
+
+        struct MetadataV1 {
+            ruby_version: String,
+        }
+
+        #[derive(CacheDiff)]
+        #[cache_diff(compare_to = MetadataV1)]
+        struct Metadata {
+            ruby_version: String,
+            #[cache_diff(added)]
+            distro: String,
+        }
+        let metadata = Metadata {
+            ruby_version: "3.4.0".to_string(),
+            distro: "Ubuntu".to_string(),
+        };
+
+        assert_eq!(
+            metadata.diff_with(&MetadataV1 {
+                ruby_version: "3.4.0".to_string(),
+            }),
+            vec!["`distro` added".to_string()]
+        );
+        assert_eq!(
+            metadata.diff(&Metadata {
+                ruby_version: "3.4.0".to_string(),
+                distro: "Alpine".to_string(),
+            }),
+            vec!["distro (`Alpine` to `Ubuntu`)".to_string()]
+        );
+    }
+
+    #[test]
+    fn compare_to_removed_field() {
+        use cache_diff::CacheDiffWith;
+
+        struct MetadataV1 {
+            ruby_version: String,
+            cpu_arch: String,
+        }
+
+        #[derive(CacheDiff)]
+        #[cache_diff(compare_to = MetadataV1, removed = cpu_arch)]
+        struct Metadata {
+            ruby_version: String,
+        }
+        let metadata = Metadata {
+            ruby_version: "3.4.0".to_string(),
+        };
+
+        assert_eq!(
+            metadata.diff_with(&MetadataV1 {
+                ruby_version: "3.3.0".to_string(),
+                cpu_arch: "arm64".to_string(),
+            }),
+            vec![
+                "ruby version (`3.3.0` to `3.4.0`)".to_string(),
+                "`cpu arch` removed".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn context_field() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(context)]
+            build_id: String,
+            version: String,
+            #[cache_diff(context, rename = "Built at")]
+            timestamp: u64,
+        }
+        let metadata = Metadata {
+            build_id: "abc".to_string(),
+            version: "3.4.0".to_string(),
+            timestamp: 2,
+        };
+
+        assert_eq!(
+            metadata.diff(&Metadata {
+                build_id: "xyz".to_string(),
+                version: "3.3.0".to_string(),
+                timestamp: 1,
+            }),
+            vec![
+                "version (`3.3.0` to `3.4.0`)".to_string(),
+                "build id: `abc`".to_string(),
+                "Built at: `2`".to_string(),
+            ]
+        );
+        assert!(metadata
+            .diff(&Metadata {
+                build_id: "xyz".to_string(),
+                version: "3.4.0".to_string(),
+                timestamp: 1,
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn sensitive_field() {
+        // Does not implement Display
+        #[derive(PartialEq)]
+        struct Token(String);
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(sensitive)]
+            api_token: Token,
+            #[cache_diff(sensitive)]
+            signed_url: String,
+        }
+        let metadata = Metadata {
+            api_token: Token("super-secret-new".to_string()),
+            signed_url: "https://example.com?sig=super-secret-url".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            api_token: Token("super-secret-old".to_string()),
+            signed_url: "https://example.com?sig=super-secret-old-url".to_string(),
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "api token (`[redacted]` to `[redacted]`)".to_string(),
+                "signed url (`[redacted]` to `[redacted]`)".to_string()
+            ]
+        );
+        let contents = diff.join(" ");
+        assert!(
+            !contents.contains("super-secret"),
+            "Unexpected contents {contents}"
+        );
+    }
+
+    #[test]
+    fn custom_line_format() {
+        #[derive(CacheDiff)]
+        #[cache_diff(align_names)]
+        struct Metadata {
+            #[cache_diff(line = "{name}: was {old}, now {now}")]
+            ruby_version: String,
+            #[cache_diff(line = "{{{name}}} is now {now}")]
+            distro: String,
+            arch: String,
+        }
+        let metadata = Metadata {
+            ruby_version: "3.4.0".to_string(),
+            distro: "ubuntu".to_string(),
+            arch: "arm64".to_string(),
+        };
+        let diff = metadata.diff(&Metadata {
+            ruby_version: "3.3.0".to_string(),
+            distro: "alpine".to_string(),
+            arch: "amd64".to_string(),
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "ruby version: was `3.3.0`, now `3.4.0`".to_string(),
+                "{distro} is now `ubuntu`".to_string(),
+                "arch         (`amd64` to `arm64`)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn presence_only() {
+        // Does not implement Display
+        #[derive(PartialEq)]
+        struct Mirror(String);
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(presence)]
+            mirror: Option<Mirror>,
+            version: String,
+        }
+        let set = |url: &str| Metadata {
+            mirror: Some(Mirror(url.to_string())),
+            version: "3.4.0".to_string(),
+        };
+        let unset = Metadata {
+            mirror: None,
+            version: "3.4.0".to_string(),
+        };
+
+        assert!(set("https://b.example.com")
+            .diff(&set("https://a.example.com"))
+            .is_empty());
+        assert_eq!(
+            set("https://a.example.com").diff(&unset),
+            vec!["mirror (`unset` to `set`)".to_string()]
+        );
+        assert_eq!(
+            unset.diff(&set("https://a.example.com")),
+            vec!["mirror (`set` to `unset`)".to_string()]
+        );
+    }
+
+    #[test]
+    fn informational_changes_do_not_invalidate() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            ruby_version: String,
+            #[cache_diff(informational)]
+            build_timestamp: String,
+        }
+        let metadata = |version: &str, timestamp: &str| Metadata {
+            ruby_version: version.to_string(),
+            build_timestamp: timestamp.to_string(),
+        };
+
+        let now = metadata("3.4.0", "2025-01-02");
+        let old = metadata("3.4.0", "2025-01-01");
+        assert_eq!(
+            now.diff(&old),
+            vec!["build timestamp (`2025-01-01` to `2025-01-02`)".to_string()]
+        );
+        assert!(!now.must_invalidate(&old));
+
+        let old = metadata("3.3.0", "2025-01-02");
+        assert_eq!(
+            now.diff(&old),
+            vec!["ruby version (`3.3.0` to `3.4.0`)".to_string()]
+        );
+        assert!(now.must_invalidate(&old));
+    }
+
+    #[test]
+    fn informational_flatten_and_all_informational() {
+        #[derive(CacheDiff)]
+        struct Inner {
+            #[cache_diff(informational)]
+            built_by: String,
+        }
+        #[derive(CacheDiff)]
+        struct Outer {
+            #[cache_diff(flatten)]
+            inner: Inner,
+        }
+        let now = Outer {
+            inner: Inner {
+                built_by: "richard".to_string(),
+            },
+        };
+        let old = Outer {
+            inner: Inner {
+                built_by: "not rich".to_string(),
+            },
+        };
+
+        assert_eq!(now.diff(&old).len(), 1);
+        assert!(!now.must_invalidate(&old));
+        assert!(!now.inner.must_invalidate(&old.inner));
+    }
+
+    #[test]
+    fn threshold_crossing() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(threshold = 512)]
+            memory_mb: u32,
+            #[cache_diff(threshold = 0.5)]
+            ratio: f64,
+        }
+        let metadata = |memory_mb, ratio| Metadata { memory_mb, ratio };
+
+        assert!(metadata(100, 0.1).diff(&metadata(256, 0.4)).is_empty());
+        assert!(metadata(600, 0.9).diff(&metadata(1024, 0.5)).is_empty());
+        assert_eq!(
+            metadata(512, 0.4).diff(&metadata(511, 0.5)),
+            vec![
+                "memory mb (`511` to `512`)".to_string(),
+                "ratio (`0.5` to `0.4`)".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_transition() {
+        #[derive(CacheDiff)]
+        #[cache_diff(transition = "→", align_names)]
+        struct Arrow {
+            version: String,
+            distro_name: String,
+        }
+        let diff = Arrow {
+            version: "3.4.0".to_string(),
+            distro_name: "ubuntu".to_string(),
+        }
+        .diff(&Arrow {
+            version: "3.3.0".to_string(),
+            distro_name: "alpine".to_string(),
+        });
+        assert_eq!(
+            diff,
+            vec![
+                "version     (`3.3.0` → `3.4.0`)".to_string(),
+                "distro name (`alpine` → `ubuntu`)".to_string()
+            ]
+        );
+
+        #[derive(CacheDiff)]
+        #[cache_diff(transition = "becomes {now}")]
+        struct Words {
+            version: String,
+            #[cache_diff(line = "{name} is {now}")]
+            distro: String,
+        }
+        let diff = Words {
+            version: "3.4.0".to_string(),
+            distro: "ubuntu".to_string(),
+        }
+        .diff(&Words {
+            version: "3.3.0".to_string(),
+            distro: "alpine".to_string(),
+        });
+        assert_eq!(
+            diff,
+            vec![
+                "version (`3.3.0` becomes {now} `3.4.0`)".to_string(),
+                "distro is `ubuntu`".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn cache_diff_fields_const() {
+        const RUBY_VERSION: &str = "Ruby version";
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(context)]
+            arch: String,
+            #[cache_diff(rename = RUBY_VERSION)]
+            version: String,
+            #[cache_diff(ignore)]
+            #[allow(dead_code)]
+            modified_by: String,
+            distro_name: String,
+        }
+        assert_eq!(
+            Metadata::CACHE_DIFF_FIELDS,
+            &["Ruby version", "distro name", "arch"]
+        );
+
+        let diff = Metadata {
+            arch: "amd64".to_string(),
+            version: "3.4.0".to_string(),
+            modified_by: "richard".to_string(),
+            distro_name: "ubuntu".to_string(),
+        }
+        .diff(&Metadata {
+            arch: "arm64".to_string(),
+            version: "3.3.0".to_string(),
+            modified_by: "not rich".to_string(),
+            distro_name: "alpine".to_string(),
+        });
+        let reported = diff
+            .iter()
+            .map(|line| {
+                Metadata::CACHE_DIFF_FIELDS
+                    .iter()
+                    .find(|name| line.starts_with(*name))
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reported, Metadata::CACHE_DIFF_FIELDS);
+    }
+
+    #[test]
+    fn debug_sorted_hash_map() {
+        use std::collections::{HashMap, HashSet};
+
+        // Only read through Debug
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        struct Tool {
+            name: String,
+            features: HashSet<&'static str>,
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(debug)]
+            triple: (u8, char),
+            #[cache_diff(debug, sorted)]
+            tools: HashMap<String, Tool>,
+        }
+        let tools = |features: &[&'static str]| {
+            (0..20)
+                .map(|i| {
+                    (
+                        format!("tool-{i}"),
+                        Tool {
+                            name: format!("{{name, with 'punctuation' {i}}}"),
+                            features: features.iter().copied().collect(),
+                        },
+                    )
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let now = Metadata {
+            triple: (1, '{'),
+            tools: tools(&["a", "b", "c", "d"]),
+        };
+
+        // Same entries built separately iterate in a different order
+        assert!(now
+            .diff(&Metadata {
+                triple: (1, '{'),
+                tools: tools(&["d", "c", "b", "a"]),
+            })
+            .is_empty());
+
+        let diff = now.diff(&Metadata {
+            triple: (2, '}'),
+            tools: tools(&["a", "b", "c"]),
+        });
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0], "triple (`(2, '}')` to `(1, '{')`)");
+        assert!(
+            diff[1].contains(
+                r#"Tool { features: {"a", "b", "c", "d"}, name: "{name, with 'punctuation' 0}" }"#
+            ),
+            "Unexpected {}",
+            diff[1]
+        );
+    }
+
+    #[test]
+    fn diff_partitioned_by_informational() {
+        #[derive(CacheDiff)]
+        struct Inner {
+            #[cache_diff(informational)]
+            built_by: String,
+            distro: String,
+        }
+        #[derive(CacheDiff)]
+        struct Metadata {
+            ruby_version: String,
+            #[cache_diff(informational)]
+            build_timestamp: String,
+            #[cache_diff(flatten)]
+            inner: Inner,
+            #[cache_diff(context)]
+            arch: String,
+        }
+        let metadata = |version: &str, timestamp: &str, built_by: &str| Metadata {
+            ruby_version: version.to_string(),
+            build_timestamp: timestamp.to_string(),
+            inner: Inner {
+                built_by: built_by.to_string(),
+                distro: "ubuntu".to_string(),
+            },
+            arch: "amd64".to_string(),
+        };
+        let now = metadata("3.4.0", "2025-01-02", "richard");
+
+        assert_eq!(
+            now.diff_partitioned(&metadata("3.4.0", "2025-01-02", "richard")),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            now.diff_partitioned(&metadata("3.4.0", "2025-01-01", "not rich")),
+            (
+                vec![],
+                vec![
+                    "build timestamp (`2025-01-01` to `2025-01-02`)".to_string(),
+                    "built by (`not rich` to `richard`)".to_string(),
+                    "arch: `amd64`".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            now.diff_partitioned(&metadata("3.3.0", "2025-01-01", "richard")),
+            (
+                vec![
+                    "ruby version (`3.3.0` to `3.4.0`)".to_string(),
+                    "arch: `amd64`".to_string()
+                ],
+                vec!["build timestamp (`2025-01-01` to `2025-01-02`)".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn zero_field_structs() {
+        use cache_diff::CacheDiffWith;
+
+        #[derive(CacheDiff)]
+        struct Named {}
+        #[derive(CacheDiff)]
+        struct Tuple();
+        #[derive(CacheDiff)]
+        struct Unit;
+        #[derive(CacheDiff)]
+        #[cache_diff(compare_to = Named)]
+        struct Other;
+
+        assert!(Named {}.diff(&Named {}).is_empty());
+        assert!(Tuple().diff(&Tuple()).is_empty());
+        assert!(Unit.diff(&Unit).is_empty());
+        assert!(Other.diff_with(&Named {}).is_empty());
+
+        assert!(Unit.changed_at_least(&Unit, 0));
+        assert!(!Unit.changed_at_least(&Unit, 1));
+        assert!(!Unit.must_invalidate(&Unit));
+        assert_eq!(Unit.diff_partitioned(&Unit), (vec![], vec![]));
+        assert!(Unit::CACHE_DIFF_FIELDS.is_empty());
+    }
+
+    #[test]
+    fn unordered_collections() {
+        use std::collections::BTreeSet;
+
+        #[derive(CacheDiff)]
+        #[cache_diff(align_names)]
+        struct Metadata {
+            #[cache_diff(unordered)]
+            features: Vec<String>,
+            #[cache_diff(unordered, display = std::path::Path::display)]
+            paths: BTreeSet<PathBuf>,
+            name: String,
+        }
+        let metadata = |features: &[&str], paths: &[&str]| Metadata {
+            features: features.iter().map(|name| name.to_string()).collect(),
+            paths: paths.iter().map(PathBuf::from).collect(),
+            name: "ruby".to_string(),
+        };
+        let now = metadata(&["yjit", "jemalloc", "yjit"], &["/a", "/b"]);
+
+        assert!(now
+            .diff(&metadata(&["yjit", "yjit", "jemalloc"], &["/b", "/a"]))
+            .is_empty());
+        assert!(!now.changed_at_least(&metadata(&["jemalloc", "yjit", "yjit"], &["/a", "/b"]), 1));
+
+        // Duplicates are counted
+        assert_eq!(
+            now.diff(&metadata(&["yjit", "jemalloc"], &["/a", "/b"])),
+            vec!["features (added `yjit`)".to_string()]
+        );
+        assert_eq!(
+            now.diff(&metadata(
+                &["debug", "jemalloc", "asan", "yjit", "yjit"],
+                &["/a", "/c"]
+            )),
+            vec![
+                "features (removed `asan`, `debug`)".to_string(),
+                "paths    (added `/b`; removed `/c`)".to_string()
+            ]
+        );
+        assert!(now.must_invalidate(&metadata(&[], &["/a", "/b"])));
+    }
+
+    #[test]
+    fn assoc_list_as_map() {
+        #[derive(CacheDiff)]
+        #[cache_diff(transition = "→")]
+        struct Metadata {
+            #[cache_diff(assoc, rename = "env var")]
+            env: Vec<(String, String)>,
+            #[cache_diff(assoc, display = std::path::Path::display)]
+            paths: Vec<(u8, PathBuf)>,
+        }
+        let metadata = |env: &[(&str, &str)], paths: &[(u8, &str)]| Metadata {
+            env: env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            paths: paths.iter().map(|(k, v)| (*k, PathBuf::from(v))).collect(),
+        };
+        let now = metadata(
+            &[("PATH", "/bin"), ("PATH", "/usr/bin"), ("LANG", "C")],
+            &[(1, "/a")],
+        );
+
+        assert!(now
+            .diff(&metadata(
+                &[("LANG", "C"), ("PATH", "/usr/bin")],
+                &[(1, "/a")]
+            ))
+            .is_empty());
+        assert!(!now.must_invalidate(&metadata(
+            &[("LANG", "C"), ("PATH", "/usr/bin")],
+            &[(1, "/a")]
+        )));
+
+        let old = metadata(
+            &[("TERM", "dumb"), ("PATH", "/bin")],
+            &[(2, "/b"), (1, "/b")],
+        );
+        assert_eq!(
+            now.diff(&old),
+            vec![
+                "env var `LANG` (added `C`)".to_string(),
+                "env var `PATH` (`/bin` → `/usr/bin`)".to_string(),
+                "env var `TERM` (removed `dumb`)".to_string(),
+                "paths `1` (`/b` → `/a`)".to_string(),
+                "paths `2` (removed `/b`)".to_string(),
+            ]
+        );
+        assert!(now.changed_at_least(&old, 5));
+        assert!(!now.changed_at_least(&old, 6));
+    }
+
+    #[test]
+    fn round_before_compare_and_display() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(round = 2)]
+            ratio: f64,
+            #[cache_diff(round = 0)]
+            load: f32,
+        }
+        let now = Metadata {
+            ratio: 1.23456,
+            load: 1.2,
+        };
+
+        assert!(now
+            .diff(&Metadata {
+                ratio: 1.23460,
+                load: 0.9,
+            })
+            .is_empty());
+        assert!(!now.must_invalidate(&Metadata {
+            ratio: 1.2349,
+            load: 1.4,
+        }));
+        assert_eq!(
+            now.diff(&Metadata {
+                ratio: 1.216,
+                load: 1.6,
+            }),
+            vec![
+                "ratio (`1.22` to `1.23`)".to_string(),
+                "load (`2` to `1`)".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn truncate_long_values() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(truncate = 5)]
+            blob: String,
+            #[cache_diff(truncate = 3)]
+            greeting: String,
+            #[cache_diff(truncate = 3, round = 2)]
+            ratio: f64,
+            #[cache_diff(truncate = 4, context)]
+            arch: String,
+        }
+        let now = Metadata {
+            blob: "aGVsbG8gd29ybGQ=".to_string(),
+            greeting: "héllo".to_string(),
+            ratio: 1.5,
+            arch: "x86_64".to_string(),
+        };
+
+        assert_eq!(
+            now.diff(&Metadata {
+                blob: "abc".to_string(),
+                greeting: "日本語です".to_string(),
+                ratio: 1.25,
+                arch: "x86_64".to_string(),
+            }),
+            vec![
+                "blob (`abc` to `aGVsb…`)".to_string(),
+                "greeting (`日本語…` to `hél…`)".to_string(),
+                "ratio (`1.2…` to `1.5…`)".to_string(),
+                "arch: `x86_…`".to_string(),
+            ]
+        );
+
+        // Values exactly at the limit aren't truncated
+        assert_eq!(
+            now.diff(&Metadata {
+                blob: "aGVsbG8gd29ybGQ=".to_string(),
+                greeting: "日本語".to_string(),
+                ratio: 1.5,
+                arch: "x86_64".to_string(),
+            }),
+            vec![
+                "greeting (`日本語` to `hél…`)".to_string(),
+                "arch: `x86_…`".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_with_runtime_names() {
+        use std::collections::BTreeMap;
+
+        #[derive(CacheDiff)]
+        struct Inner {
+            distro: String,
+        }
+
+        #[derive(CacheDiff)]
+        #[cache_diff(align_names)]
+        struct Metadata {
+            #[cache_diff(rename = "Ruby version")]
+            ruby_version: String,
+            #[cache_diff(flatten)]
+            inner: Inner,
+            #[cache_diff(context)]
+            r#type: String,
+        }
+        let now = Metadata {
+            ruby_version: "3.4.0".to_string(),
+            inner: Inner {
+                distro: "Ubuntu".to_string(),
+            },
+            r#type: "x86_64".to_string(),
+        };
+        let old = Metadata {
+            ruby_version: "3.3.0".to_string(),
+            inner: Inner {
+                distro: "Alpine".to_string(),
+            },
+            r#type: "x86_64".to_string(),
+        };
+
+        let names = BTreeMap::from([
+            ("ruby_version", "Ruby-Version"),
+            ("distro", "Distribution"),
+            ("type", "Architektur"),
+        ]);
+        assert_eq!(
+            now.diff_with_names(&old, &names),
+            vec![
+                "Ruby-Version (`3.3.0` to `3.4.0`)".to_string(),
+                "Distribution (`Alpine` to `Ubuntu`)".to_string(),
+                "Architektur: `x86_64`".to_string(),
+            ]
+        );
+        assert_eq!(now.diff_with_names(&old, &BTreeMap::new()), now.diff(&old));
+    }
+
+    #[test]
+    fn cfg_gated_fields() {
+        // Run with and without `--features cfg_field`
+        #[derive(CacheDiff)]
+        #[cache_diff(align_names)]
+        struct Metadata {
+            version: String,
+            #[cfg(feature = "cfg_field")]
+            #[cache_diff(group = "Gated", weight = 1)]
+            gated_name: String,
+            #[cfg(not(feature = "cfg_field"))]
+            #[cache_diff(rename = "gated name")]
+            fallback: u32,
+            #[cfg(feature = "cfg_field")]
+            #[cache_diff(context)]
+            arch: String,
+        }
+        let now = Metadata {
+            version: "3.4.0".to_string(),
+            #[cfg(feature = "cfg_field")]
+            gated_name: "new".to_string(),
+            #[cfg(not(feature = "cfg_field"))]
+            fallback: 2,
+            #[cfg(feature = "cfg_field")]
+            arch: "amd64".to_string(),
+        };
+        let old = Metadata {
+            version: "3.3.0".to_string(),
+            #[cfg(feature = "cfg_field")]
+            gated_name: "old".to_string(),
+            #[cfg(not(feature = "cfg_field"))]
+            fallback: 1,
+            #[cfg(feature = "cfg_field")]
+            arch: "amd64".to_string(),
+        };
+
+        if cfg!(feature = "cfg_field") {
+            assert_eq!(
+                Metadata::CACHE_DIFF_FIELDS,
+                &["version", "gated name", "arch"]
+            );
+            assert_eq!(
+                now.diff(&old),
+                vec![
+                    "version    (`3.3.0` to `3.4.0`)".to_string(),
+                    "gated name (`old` to `new`)".to_string(),
+                    "arch: `amd64`".to_string(),
+                ]
+            );
+        } else {
+            assert_eq!(Metadata::CACHE_DIFF_FIELDS, &["version", "gated name"]);
+            assert_eq!(
+                now.diff(&old),
+                vec![
+                    "version    (`3.3.0` to `3.4.0`)".to_string(),
+                    "gated name (`1` to `2`)".to_string(),
+                ]
+            );
+        }
+        assert!(now.changed_at_least(&old, 2));
+        assert!(now.must_invalidate(&old));
+        assert_eq!(now.diff_weighted(&old).len(), now.diff(&old).len());
+        assert_eq!(now.diff_partitioned(&old).0, now.diff(&old));
+    }
+
+    #[test]
+    fn merge_differences() {
+        assert!(cache_diff::merge(Vec::<Vec<String>>::new()).is_empty());
+        assert!(cache_diff::merge([vec![], vec![]]).is_empty());
+        assert_eq!(
+            cache_diff::merge([
+                vec!["b".to_string(), "a".to_string(), "b".to_string()],
+                vec![],
+                vec!["c".to_string(), "a".to_string()],
+            ]),
+            vec!["b".to_string(), "a".to_string(), "c".to_string()]
+        );
+    }
+
+    #[test]
+    fn reference_fields() {
+        fn shout(value: &str) -> String {
+            value.to_uppercase()
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            distro: &'static str,
+            #[cache_diff(display = shout)]
+            arch: &'static str,
+        }
+        let diff = Metadata {
+            distro: "ubuntu",
+            arch: "arm64",
+        }
+        .diff(&Metadata {
+            distro: "alpine",
+            arch: "amd64",
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "distro (`alpine` to `ubuntu`)".to_string(),
+                "arch (`AMD64` to `ARM64`)".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_with_without_partial_eq() {
+        // Deliberately implements neither `PartialEq` nor `Eq`
+        struct Version {
+            major: u32,
+            minor: u32,
+        }
+        impl std::fmt::Display for Version {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}.{}", self.major, self.minor)
+            }
+        }
+        fn same_major(old: &Version, now: &Version) -> bool {
+            old.major == now.major
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(compare_with = same_major, informational)]
+            version: Version,
+            #[cache_diff(context)]
+            distro: String,
+        }
+        let metadata = |major: u32, minor: u32| Metadata {
+            version: Version { major, minor },
+            distro: "Ubuntu".to_string(),
+        };
+        let now = metadata(3, 4);
+
+        assert!(now.diff(&metadata(3, 3)).is_empty());
+        assert!(!now.changed_at_least(&metadata(3, 3), 1));
+        assert_eq!(
+            now.diff(&metadata(2, 7)),
+            vec![
+                "version (`2.7` to `3.4`)".to_string(),
+                "distro: `Ubuntu`".to_string()
+            ]
+        );
+        assert!(now.changed_at_least(&metadata(2, 7), 1));
+        assert!(!now.must_invalidate(&metadata(2, 7)));
+        assert_eq!(now.diff_partitioned(&metadata(2, 7)).1.len(), 2);
+    }
+
+    #[test]
+    fn diff_with_options_toggles() {
+        use cache_diff::CacheDiffOptions;
+
+        #[derive(CacheDiff)]
+        struct Inner {
+            #[cache_diff(informational)]
+            built_at: String,
+        }
+        // Flattened structs keep their own transition unless it's overridden
+        #[derive(CacheDiff)]
+        #[cache_diff(transition = "becomes")]
+        struct Metadata {
+            #[cache_diff(truncate = 6)]
+            checksum: String,
+            #[cache_diff(assoc)]
+            env: Vec<(String, String)>,
+            #[cache_diff(informational)]
+            notes: String,
+            #[cache_diff(flatten)]
+            inner: Inner,
+        }
+        let metadata = |checksum: &str, value: &str, notes: &str, built_at: &str| Metadata {
+            checksum: checksum.to_string(),
+            env: vec![("PATH".to_string(), value.to_string())],
+            notes: notes.to_string(),
+            inner: Inner {
+                built_at: built_at.to_string(),
+            },
+        };
+        let now = metadata("a1b2c3d4e5", "/usr/local/bin", "fast", "Tuesday");
+        let old = metadata("f6a7b8c9d0", "/usr/bin", "slow", "Monday");
+
+        let default = CacheDiffOptions::default();
+        assert_eq!(default.transition(), None);
+        assert!(default.include_informational());
+        assert_eq!(default.max_value_len(), None);
+        assert_eq!(now.diff_with_options(&old, &default), now.diff(&old));
+        assert_eq!(
+            now.diff(&old),
+            vec![
+                "checksum (`f6a7b8…` becomes `a1b2c3…`)".to_string(),
+                "env `PATH` (`/usr/bin` becomes `/usr/local/bin`)".to_string(),
+                "notes (`slow` becomes `fast`)".to_string(),
+                "built at (`Monday` to `Tuesday`)".to_string(),
+            ]
+        );
+
+        assert_eq!(
+            now.diff_with_options(&old, &CacheDiffOptions::default().with_transition("→")),
+            vec![
+                "checksum (`f6a7b8…` → `a1b2c3…`)".to_string(),
+                "env `PATH` (`/usr/bin` → `/usr/local/bin`)".to_string(),
+                "notes (`slow` → `fast`)".to_string(),
+                "built at (`Monday` → `Tuesday`)".to_string(),
+            ]
+        );
+        assert_eq!(
+            now.diff_with_options(&old, &CacheDiffOptions::default().with_informational(false)),
+            vec![
+                "checksum (`f6a7b8…` becomes `a1b2c3…`)".to_string(),
+                "env `PATH` (`/usr/bin` becomes `/usr/local/bin`)".to_string(),
+            ]
+        );
+        // The smaller of the option and the field's `truncate` applies
+        assert_eq!(
+            now.diff_with_options(&old, &CacheDiffOptions::default().with_max_value_len(8)),
+            vec![
+                "checksum (`f6a7b8…` becomes `a1b2c3…`)".to_string(),
+                "env `PATH` (`/usr/bin` becomes `/usr/loc…`)".to_string(),
+                "notes (`slow` becomes `fast`)".to_string(),
+                "built at (`Monday` to `Tuesday`)".to_string(),
+            ]
+        );
+        assert_eq!(
+            now.diff_with_options(&old, &CacheDiffOptions::default().with_max_value_len(2))[0],
+            "checksum (`f6…` becomes `a1…`)"
+        );
+    }
+
+    #[test]
+    fn split_field_attributes() {
+        // Display functions receive a reference to the field type
+        #[allow(clippy::ptr_arg)]
+        fn major(version: &String) -> &str {
+            version.split('.').next().unwrap_or_default()
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(rename = "Ruby")]
+            #[cache_diff(display = major)]
+            version: String,
+        }
+        let diff = Metadata {
+            version: "3.4.0".to_string(),
+        }
+        .diff(&Metadata {
+            version: "2.7.0".to_string(),
+        });
+
+        assert_eq!(diff, vec!["Ruby (`2` to `3`)".to_string()]);
+    }
+
+    #[test]
+    fn display_old_and_new() {
+        // Old caches stored versions with a `v` prefix
+        #[allow(clippy::ptr_arg)]
+        fn legacy(version: &String) -> &str {
+            version.trim_start_matches('v')
+        }
+        fn kib(bytes: &u64) -> String {
+            format!("{}KiB", bytes / 1024)
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(display_old = legacy)]
+            version: String,
+            #[cache_diff(display_new = kib)]
+            size: u64,
+            #[cache_diff(unordered, display_old = legacy)]
+            gems: Vec<String>,
+        }
+        let diff = Metadata {
+            version: "3.4.0".to_string(),
+            size: 2048,
+            gems: vec!["rake".to_string()],
+        }
+        .diff(&Metadata {
+            version: "v3.3.0".to_string(),
+            size: 1024,
+            gems: vec!["vrails".to_string()],
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "version (`3.3.0` to `3.4.0`)".to_string(),
+                "size (`1024` to `2KiB`)".to_string(),
+                "gems (added `rake`; removed `rails`)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_at_least_stops_in_flattened_fields() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COMPARED: AtomicUsize = AtomicUsize::new(0);
+        fn counted_eq(old: &String, now: &String) -> bool {
+            COMPARED.fetch_add(1, Ordering::SeqCst);
+            old == now
+        }
+
+        #[derive(CacheDiff)]
+        struct Inner {
+            #[cache_diff(compare_with = counted_eq)]
+            first: String,
+            #[cache_diff(compare_with = counted_eq)]
+            second: String,
+            #[cache_diff(compare_with = counted_eq)]
+            third: String,
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            version: String,
+            #[cache_diff(flatten)]
+            inner: Inner,
+        }
+        let metadata = |value: &str| Metadata {
+            version: value.to_string(),
+            inner: Inner {
+                first: value.to_string(),
+                second: value.to_string(),
+                third: value.to_string(),
+            },
+        };
+        let (now, old) = (metadata("new"), metadata("old"));
+
+        assert!(now.changed_at_least(&old, 2));
+        assert_eq!(COMPARED.load(Ordering::SeqCst), 1);
+
+        COMPARED.store(0, Ordering::SeqCst);
+        assert_eq!(now.changes_up_to(&old, 10), 4);
+        assert_eq!(COMPARED.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn grouped_diff_includes_context() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(group = "Ruby")]
+            version: String,
+            #[cache_diff(group = "Ruby")]
+            bundler: String,
+            distro: String,
+            #[cache_diff(context)]
+            build_id: String,
+        }
+        let metadata = |version: &str, distro: &str| Metadata {
+            version: version.to_string(),
+            bundler: "2.5".to_string(),
+            distro: distro.to_string(),
+            build_id: "abc".to_string(),
+        };
+        let now = metadata("3.4.0", "Ubuntu");
+
+        assert_eq!(
+            now.grouped_diff(&metadata("3.3.0", "Ubuntu")),
+            vec![
+                (
+                    "Ruby".to_string(),
+                    vec!["version (`3.3.0` to `3.4.0`)".to_string()]
+                ),
+                (String::new(), vec!["build id: `abc`".to_string()]),
+            ]
+        );
+        assert_eq!(
+            now.grouped_diff(&metadata("3.4.0", "Alpine")),
+            vec![(
+                String::new(),
+                vec![
+                    "distro (`Alpine` to `Ubuntu`)".to_string(),
+                    "build id: `abc`".to_string()
+                ]
+            )]
+        );
+        assert!(now.grouped_diff(&metadata("3.4.0", "Ubuntu")).is_empty());
+    }
+
+    #[test]
+    fn diff_summary_counts_fields() {
+        #[derive(CacheDiff)]
+        struct Inner {
+            distro: String,
+            arch: String,
+        }
+        #[derive(CacheDiff)]
+        struct Metadata {
+            version: String,
+            #[cache_diff(informational)]
+            built_at: String,
+            #[cache_diff(ignore)]
+            #[allow(dead_code)]
+            modified_by: String,
+            #[cache_diff(context)]
+            stack: String,
+            #[cache_diff(unordered)]
+            features: Vec<String>,
+            #[cache_diff(flatten)]
+            inner: Inner,
+        }
+        let metadata = |version: &str, built_at: &str, feature: &str, distro: &str| Metadata {
+            version: version.to_string(),
+            built_at: built_at.to_string(),
+            modified_by: "Schneems".to_string(),
+            stack: "heroku-24".to_string(),
+            features: vec![feature.to_string()],
+            inner: Inner {
+                distro: distro.to_string(),
+                arch: "amd64".to_string(),
+            },
+        };
+        let now = metadata("3.4.0", "Tuesday", "yjit", "Ubuntu");
+
+        assert_eq!(now.diff_summary(&now), (0, 5));
+        assert_eq!(
+            now.diff_summary(&metadata("3.3.0", "Monday", "yjit", "Ubuntu")),
+            (2, 5)
+        );
+        assert_eq!(
+            now.diff_summary(&metadata("3.3.0", "Monday", "mjit", "Alpine")),
+            (4, 5)
+        );
+
+        // Manual implementations fall back to counting differences
+        struct Manual;
+        impl CacheDiff for Manual {
+            fn diff(&self, _old: &Self) -> Vec<String> {
+                vec!["a".to_string(), "b".to_string()]
+            }
+        }
+        assert_eq!(Manual.diff_summary(&Manual), (2, 2));
+    }
+
+    #[test]
+    fn marker_fields_are_ignored() {
+        use std::marker::PhantomData;
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            version: String,
+            format: PhantomData<String>,
+            #[allow(dead_code)]
+            sealed: (),
+        }
+        let metadata = |version: &str| Metadata {
+            version: version.to_string(),
+            format: PhantomData,
+            sealed: (),
+        };
+
+        assert_eq!(Metadata::CACHE_DIFF_FIELDS, &["version"]);
+        assert_eq!(
+            metadata("3.4.0").diff(&metadata("3.3.0")),
+            vec!["version (`3.3.0` to `3.4.0`)".to_string()]
+        );
+    }
+
+    #[test]
+    fn try_display_fallback() {
+        #[allow(clippy::ptr_arg)]
+        fn parse_port(port: &String) -> Result<u16, std::num::ParseIntError> {
+            port.parse()
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(try_display = parse_port)]
+            port: String,
+        }
+        let metadata = |port: &str| Metadata {
+            port: port.to_string(),
+        };
+
+        assert_eq!(
+            metadata("8080").diff(&metadata("80")),
+            vec!["port (`80` to `8080`)".to_string()]
+        );
+        assert_eq!(
+            metadata("8080").diff(&metadata("http")),
+            vec!["port (`<unrenderable>` to `8080`)".to_string()]
+        );
+        assert_eq!(
+            metadata("https").diff(&metadata("http")),
+            vec!["port (`<unrenderable>` to `<unrenderable>`)".to_string()]
+        );
+        assert!(metadata("http").diff(&metadata("http")).is_empty());
+    }
+
+    #[test]
+    fn field_aliases_const() {
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(alias = "version", alias = "ruby")]
+            #[cache_diff(rename = "Ruby version")]
+            ruby_version: String,
+            distro: String,
+            #[cache_diff(ignore, alias = "user")]
+            #[allow(dead_code)]
+            r#modified_by: String,
+        }
+
+        assert_eq!(
+            Metadata::CACHE_DIFF_ALIASES,
+            &[
+                ("ruby_version", &["version", "ruby"][..]),
+                ("modified_by", &["user"][..])
+            ]
+        );
+
+        #[derive(CacheDiff)]
+        struct Empty {}
+        assert!(Empty::CACHE_DIFF_ALIASES.is_empty());
+    }
+
+    #[test]
+    fn ignored_reasons_const() {
+        #[derive(CacheDiff)]
+        #[allow(dead_code)]
+        struct Metadata {
+            version: String,
+            #[cache_diff(ignore = "set by CI, not cache-relevant")]
+            r#build_number: u64,
+            #[cache_diff(ignore)]
+            changed_by: String,
+            marker: std::marker::PhantomData<String>,
+        }
+
+        assert_eq!(
+            Metadata::CACHE_DIFF_IGNORED,
+            &[
+                ("build_number", "set by CI, not cache-relevant"),
+                ("changed_by", "")
+            ]
+        );
+
+        #[derive(CacheDiff)]
+        struct Empty {}
+        assert!(Empty::CACHE_DIFF_IGNORED.is_empty());
+    }
+
+    #[test]
+    fn custom_hook_runs_after_fields() {
+        fn policy(now: &Metadata, old: &Metadata) -> Vec<String> {
+            if (now.strict, now.level) != (old.strict, old.level) {
+                vec!["rebuild forced by policy change".to_string()]
+            } else {
+                Vec::new()
+            }
+        }
+
+        #[derive(CacheDiff)]
+        #[cache_diff(custom = policy)]
+        struct Metadata {
+            #[cache_diff(group = "Ruby")]
+            version: String,
+            #[cache_diff(ignore)]
+            strict: bool,
+            #[cache_diff(ignore)]
+            level: u8,
+            #[cache_diff(context)]
+            distro: String,
+        }
+        let metadata = |version: &str, level: u8| Metadata {
+            version: version.to_string(),
+            strict: true,
+            level,
+            distro: "Ubuntu".to_string(),
+        };
+        let now = metadata("3.4.0", 2);
+
+        assert_eq!(
+            now.diff(&metadata("3.3.0", 1)),
+            vec![
+                "version (`3.3.0` to `3.4.0`)".to_string(),
+                "rebuild forced by policy change".to_string(),
+                "distro: `Ubuntu`".to_string(),
+            ]
+        );
+        assert_eq!(
+            now.diff(&metadata("3.4.0", 1)),
+            vec![
+                "rebuild forced by policy change".to_string(),
+                "distro: `Ubuntu`".to_string(),
+            ]
+        );
+        assert!(now.diff(&metadata("3.4.0", 2)).is_empty());
+        assert!(now.must_invalidate(&metadata("3.4.0", 1)));
+        assert!(now.changed_at_least(&metadata("3.3.0", 1), 2));
+        assert_eq!(
+            now.grouped_diff(&metadata("3.3.0", 1)),
+            vec![
+                (
+                    "Ruby".to_string(),
+                    vec!["version (`3.3.0` to `3.4.0`)".to_string()]
+                ),
+                (
+                    String::new(),
+                    vec![
+                        "rebuild forced by policy change".to_string(),
+                        "distro: `Ubuntu`".to_string(),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    // Run with `--features bullet_stream`, the other tests expect values without colors
+    #[cfg(feature = "bullet_stream")]
+    mod bullet_stream {
+        use cache_diff::CacheDiff;
+
+        #[test]
+        fn diff_plain_and_styled() {
+            #[derive(CacheDiff)]
+            struct Metadata {
+                version: String,
+                distro: String,
+            }
+            let now = Metadata {
+                version: "3.4.0".to_string(),
+                distro: "Ubuntu".to_string(),
+            };
+            let pairs = now.diff_plain_and_styled(&Metadata {
+                version: "3.3.0".to_string(),
+                distro: "Ubuntu".to_string(),
+            });
+
+            assert_eq!(pairs.len(), 1);
+            let (plain, styled) = &pairs[0];
+            assert_eq!(plain, "version (`3.3.0` to `3.4.0`)");
+            assert!(!plain.contains('\x1b'));
+            assert!(styled.contains('\x1b'));
+            assert_eq!(
+                styled,
+                &now.diff(&Metadata {
+                    version: "3.3.0".to_string(),
+                    distro: "Ubuntu".to_string(),
+                })[0]
+            );
+        }
+
+        #[test]
+        fn old_and_new_values_keep_fmt_value() {
+            struct Metadata {
+                version: String,
+            }
+            impl CacheDiff for Metadata {
+                fn diff(&self, old: &Self) -> Vec<String> {
+                    vec![format!(
+                        "version {} to {}",
+                        self.fmt_old_value(&old.version),
+                        self.fmt_new_value(&self.version)
+                    )]
+                }
+
+                // Purple, the override's colors are kept
+                fn fmt_value<T: std::fmt::Display>(&self, value: &T) -> String {
+                    format!("<\x1B[0;35m{value}\x1B[0m>")
+                }
+            }
+            let now = Metadata {
+                version: "3.4.0".to_string(),
+            };
+            let old = Metadata {
+                version: "3.3.0".to_string(),
+            };
+
+            assert_eq!(
+                now.diff(&old),
+                vec![
+                    "version <\x1B[0;35m3.3.0\x1B[0m> to \x1B[1;36m<\x1B[0;35m3.4.0\x1B[0m\x1B[1;36m>\x1B[0m"
+                        .to_string()
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn gate_field_replaces_other_differences() {
+        #[derive(CacheDiff)]
+        #[cache_diff(gate = schema_version)]
+        struct Metadata {
+            schema_version: u32,
+            version: String,
+            #[cache_diff(informational)]
+            built_at: String,
+        }
+        let metadata = |schema_version: u32, version: &str| Metadata {
+            schema_version,
+            version: version.to_string(),
+            built_at: "Monday".to_string(),
+        };
+        let now = metadata(2, "3.4.0");
+
+        let old = metadata(1, "3.3.0");
+        assert_eq!(
+            now.diff(&old),
+            vec!["cache schema changed (`1` to `2`)".to_string()]
+        );
+        assert!(now.must_invalidate(&old));
+        assert!(now.changed_at_least(&old, 1));
+        assert!(!now.changed_at_least(&old, 2));
+        assert_eq!(now.diff_summary(&old), (1, 3));
+        assert_eq!(
+            now.diff_partitioned(&old),
+            (
+                vec!["cache schema changed (`1` to `2`)".to_string()],
+                Vec::new()
+            )
+        );
+
+        let old = metadata(2, "3.3.0");
+        assert_eq!(
+            now.diff(&old),
+            vec!["version (`3.3.0` to `3.4.0`)".to_string()]
+        );
+        assert_eq!(now.diff_summary(&old), (1, 3));
+        assert!(now.diff(&metadata(2, "3.4.0")).is_empty());
+    }
+
+    #[test]
+    fn gate_field_with_compare_to() {
+        use cache_diff::CacheDiffWith;
+
+        struct OldMetadata {
+            schema_version: u32,
+            version: String,
+        }
+
+        #[derive(CacheDiff)]
+        #[cache_diff(gate = schema_version, compare_to = OldMetadata)]
+        struct Metadata {
+            schema_version: u32,
+            version: String,
+        }
+        let now = Metadata {
+            schema_version: 2,
+            version: "3.4.0".to_string(),
+        };
+
+        assert_eq!(
+            now.diff_with(&OldMetadata {
+                schema_version: 1,
+                version: "3.3.0".to_string(),
+            }),
+            vec!["cache schema changed (`1` to `2`)".to_string()]
+        );
+        assert_eq!(
+            now.diff_with(&OldMetadata {
+                schema_version: 2,
+                version: "3.3.0".to_string(),
+            }),
+            vec!["version (`3.3.0` to `3.4.0`)".to_string()]
+        );
+    }
+
+    #[test]
+    fn write_diff_to_file() {
+        use std::io::Write;
+
+        #[derive(CacheDiff)]
+        struct Inner {
+            distro: String,
+        }
+        #[derive(CacheDiff)]
+        struct Metadata {
+            version: String,
+            #[cache_diff(flatten)]
+            inner: Inner,
+            #[cache_diff(context)]
+            arch: String,
+        }
+        let metadata = |version: &str, distro: &str| Metadata {
+            version: version.to_string(),
+            inner: Inner {
+                distro: distro.to_string(),
+            },
+            arch: "amd64".to_string(),
+        };
+        let now = metadata("3.4.0", "Ubuntu");
+        let old = metadata("3.3.0", "Alpine");
+
+        let path = std::env::temp_dir().join(format!("cache_diff_write_{}", std::process::id()));
+        let mut file = std::fs::File::create(&path).unwrap();
+        now.write_diff_to(&old, &mut file).unwrap();
+        now.write_diff_to(&now, &mut file).unwrap();
+        drop(file);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, format!("{}\n", now.diff(&old).join("\n")));
+
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("broken pipe"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let error = now.write_diff_to(&old, &mut Broken).unwrap_err();
+        assert_eq!(error.to_string(), "broken pipe");
+    }
+
+    #[derive(CacheDiff)]
+    struct AssertMetadata {
+        version: String,
+    }
+
+    fn assert_metadata(version: &str) -> AssertMetadata {
+        AssertMetadata {
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn assert_no_diff_passes() {
+        cache_diff::assert_no_diff!(assert_metadata("3.4.0"), assert_metadata("3.4.0"));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected no differences, found 1:\n  - version (`3.3.0` to `3.4.0`)"
+    )]
+    fn assert_no_diff_fails() {
+        cache_diff::assert_no_diff!(assert_metadata("3.4.0"), assert_metadata("3.3.0"));
+    }
+
+    #[test]
+    fn assert_diff_passes() {
+        cache_diff::assert_diff!(
+            assert_metadata("3.4.0"),
+            assert_metadata("3.3.0"),
+            ["version (`3.3.0` to `3.4.0`)"]
+        );
+        cache_diff::assert_diff!(assert_metadata("3.4.0"), assert_metadata("3.4.0"), []);
+    }
+
+    #[test]
+    #[should_panic(expected = "differences do not match")]
+    fn assert_diff_fails() {
+        cache_diff::assert_diff!(
+            assert_metadata("3.4.0"),
+            assert_metadata("3.3.0"),
+            ["version (`3.3.0` to `3.5.0`)"]
+        );
+    }
+
+    #[test]
+    fn display_only_runs_for_shown_values() {
+        use std::cell::Cell;
+
+        thread_local! {
+            static DISPLAY_CALLS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        // Stands in for an expensive display function
+        #[allow(clippy::ptr_arg)]
+        fn slow(value: &String) -> String {
+            DISPLAY_CALLS.with(|calls| calls.set(calls.get() + 1));
+            value.to_uppercase()
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(display = slow)]
+            version: String,
+            #[cache_diff(display = slow)]
+            distro: String,
+            #[cache_diff(display = slow, line = "{name} is now {now}")]
+            arch: String,
+        }
+        let now = Metadata {
+            version: "3.4.0".to_string(),
+            distro: "ubuntu".to_string(),
+            arch: "arm64".to_string(),
+        };
+        let old = Metadata {
+            version: "3.4.0".to_string(),
+            distro: "alpine".to_string(),
+            arch: "amd64".to_string(),
+        };
+
+        assert!(now.diff(&now).is_empty());
+        assert!(now.changed_at_least(&old, 2));
+        assert!(now.must_invalidate(&old));
+        assert_eq!(now.diff_summary(&old), (2, 3));
+        assert_eq!(DISPLAY_CALLS.with(Cell::get), 0);
+
+        // Both sides of `distro`, only the new side of `arch`, nothing for the unchanged `version`
+        assert_eq!(
+            now.diff(&old),
+            vec![
+                "distro (`ALPINE` to `UBUNTU`)".to_string(),
+                "arch is now `ARM64`".to_string()
+            ]
+        );
+        assert_eq!(DISPLAY_CALLS.with(Cell::get), 3);
+    }
+
+    #[test]
+    fn changed_at_least_stops_early() {
+        use std::cell::Cell;
+
+        thread_local! {
+            static EQ_CALLS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct Counted(u32);
+        impl PartialEq for Counted {
+            fn eq(&self, other: &Self) -> bool {
+                EQ_CALLS.with(|calls| calls.set(calls.get() + 1));
+                self.0 == other.0
+            }
+        }
+        impl std::fmt::Display for Counted {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            a: Counted,
+            b: Counted,
+            c: Counted,
+            d: Counted,
+        }
+        let now = Metadata {
+            a: Counted(1),
+            b: Counted(0),
+            c: Counted(1),
+            d: Counted(1),
+        };
+        let old = Metadata {
+            a: Counted(0),
+            b: Counted(0),
+            c: Counted(0),
+            d: Counted(0),
+        };
+
+        assert!(now.changed_at_least(&old, 0));
+        assert_eq!(EQ_CALLS.with(Cell::get), 0);
+
+        assert!(now.changed_at_least(&old, 2));
+        assert_eq!(EQ_CALLS.with(Cell::get), 3);
+
+        EQ_CALLS.with(|calls| calls.set(0));
+        assert!(!now.changed_at_least(&old, 4));
+        assert_eq!(EQ_CALLS.with(Cell::get), 4);
+    }
+
+    #[test]
+    fn display_returns_impl_display() {
+        struct Quoted<'a>(&'a str);
+        impl std::fmt::Display for Quoted<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "'{}'", self.0)
+            }
+        }
+        // Display functions receive a reference to the field type
+        #[allow(clippy::ptr_arg)]
+        fn quoted(value: &String) -> Quoted<'_> {
+            Quoted(value)
+        }
+        fn on_off(value: &bool) -> &'static str {
+            if *value {
+                "on"
+            } else {
+                "off"
+            }
+        }
+
+        #[derive(CacheDiff)]
+        struct Metadata {
+            #[cache_diff(display = quoted)]
+            version: String,
+            #[cache_diff(display = on_off)]
+            jit: bool,
+        }
+        let metadata = Metadata {
+            version: "3.4.0".to_string(),
+            jit: true,
+        };
+        let diff = metadata.diff(&Metadata {
+            version: "3.3.0".to_string(),
+            jit: false,
+        });
+
+        assert_eq!(
+            diff,
+            vec![
+                "version (`'3.3.0'` to `'3.4.0'`)".to_string(),
+                "jit (`off` to `on`)".to_string()
+            ]
+        );
+    }
+
     // #[test]
     // fn ignore_field() {
     //     #[derive(CacheDiff)]
@@ -166,3 +2405,127 @@ mod tests {
         assert_eq!(diff.len(), 1);
     }
 }
+
+// Checked by `cargo clippy --all-targets`, generated code must not trip pedantic lints
+#[cfg(test)]
+#[deny(
+    warnings,
+    clippy::pedantic,
+    unused_qualifications,
+    unused_extern_crates
+)]
+mod strict_lints {
+    use cache_diff::{CacheDiff, CacheDiffWith};
+
+    #[derive(CacheDiff)]
+    #[cache_diff(align_names)]
+    struct Metadata {
+        #[cache_diff(rename = "Ruby version", alias = "ruby")]
+        version: String,
+        #[cache_diff(generation, group = "Build")]
+        generation: u32,
+        #[cache_diff(weight = 10)]
+        distro: String,
+        #[cache_diff(context)]
+        arch: String,
+        #[cache_diff(presence)]
+        mirror: Option<String>,
+        #[cache_diff(sensitive)]
+        token: String,
+        #[cache_diff(line = "{name} is now {now}")]
+        stack: String,
+    }
+
+    #[derive(CacheDiff)]
+    struct Outer {
+        #[cache_diff(flatten)]
+        inner: Metadata,
+    }
+
+    #[derive(CacheDiff)]
+    #[cache_diff(gate = schema_version, custom = policy)]
+    struct Versioned {
+        schema_version: u32,
+        #[cache_diff(round = 2)]
+        ratio: f64,
+        #[cache_diff(debug, sorted)]
+        gems: Vec<String>,
+        #[cache_diff(informational)]
+        built_at: String,
+        #[cache_diff(ignore = "set by CI, not cache-relevant")]
+        build_number: u64,
+        #[cache_diff(line = "changed to {now}")]
+        checksum: String,
+    }
+
+    fn policy(now: &Versioned, old: &Versioned) -> Vec<String> {
+        if now.build_number < old.build_number {
+            vec!["build number went backwards".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    struct MetadataV1 {
+        version: String,
+        arch: String,
+    }
+
+    #[derive(CacheDiff)]
+    #[cache_diff(compare_to = MetadataV1, removed = arch)]
+    struct MetadataV2 {
+        version: String,
+        #[cache_diff(added)]
+        distro: String,
+    }
+
+    #[derive(CacheDiff)]
+    struct Named {}
+
+    #[derive(CacheDiff)]
+    struct Tuple();
+
+    #[derive(CacheDiff)]
+    #[cache_diff(compare_to = Named)]
+    struct Unit;
+
+    #[test]
+    fn strict_lints_compile() {
+        assert!(Named {}.diff(&Named {}).is_empty());
+        assert!(Tuple().diff(&Tuple()).is_empty());
+        assert!(Unit.diff(&Unit).is_empty());
+
+        let outer = Outer {
+            inner: Metadata {
+                version: "3.4.0".to_string(),
+                generation: 1,
+                distro: "ubuntu".to_string(),
+                arch: "amd64".to_string(),
+                mirror: None,
+                token: "secret".to_string(),
+                stack: "heroku-24".to_string(),
+            },
+        };
+        assert!(outer.diff(&outer).is_empty());
+
+        let versioned = Versioned {
+            schema_version: 1,
+            ratio: 0.5,
+            gems: Vec::new(),
+            built_at: "Monday".to_string(),
+            build_number: 1,
+            checksum: "abc".to_string(),
+        };
+        assert!(versioned.diff(&versioned).is_empty());
+
+        let metadata = MetadataV2 {
+            version: "3.4.0".to_string(),
+            distro: "ubuntu".to_string(),
+        };
+        let old = MetadataV1 {
+            version: "3.4.0".to_string(),
+            arch: "amd64".to_string(),
+        };
+        assert_eq!(metadata.diff_with(&old).len(), 2);
+    }
+}
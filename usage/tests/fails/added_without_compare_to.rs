@@ -0,0 +1,10 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+struct Metadata {
+    version: String,
+    #[cache_diff(added)]
+    distro: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+struct Example {
+    #[cache_diff(ignore)]
+    modified_by: String,
+}
+
+fn main() {}
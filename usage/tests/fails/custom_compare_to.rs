@@ -0,0 +1,17 @@
+use cache_diff::CacheDiff;
+
+struct OldMetadata {
+    version: String,
+}
+
+#[derive(CacheDiff)]
+#[cache_diff(custom = policy, compare_to = OldMetadata)]
+struct Metadata {
+    version: String,
+}
+
+fn policy(_now: &Metadata, _old: &Metadata) -> Vec<String> {
+    Vec::new()
+}
+
+fn main() {}
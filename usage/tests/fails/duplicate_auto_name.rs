@@ -0,0 +1,10 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+struct Example {
+    ruby_version: String,
+    #[cache_diff(rename = "ruby version")]
+    version: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+#[cache_diff(transition = "→")]
+#[cache_diff(align_names, transition = "->")]
+struct Metadata {
+    version: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+#[cache_diff(word_separator = "_", word_separator = "-")]
+struct Metadata {
+    ruby_version: String,
+}
+
+fn main() {}
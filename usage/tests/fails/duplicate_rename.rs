@@ -0,0 +1,11 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+struct Example {
+    #[cache_diff(rename = "version")]
+    ruby_version: String,
+    #[cache_diff(rename = "version")]
+    bundler_version: String,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use cache_diff::CacheDiff;
+
+// Only fields that are both behind a `cfg` may share a name
+#[derive(CacheDiff)]
+struct Example {
+    #[cache_diff(rename = "version")]
+    ruby_version: String,
+    #[cfg(all())]
+    #[cache_diff(rename = "version")]
+    bundler_version: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+enum Example {
+    Ruby,
+    Python,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use cache_diff::CacheDiff;
+use std::collections::HashSet;
+
+#[derive(CacheDiff)]
+struct Metadata {
+    #[cache_diff(line = "{name} changed")]
+    #[cache_diff(unordered)]
+    gems: HashSet<String>,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use cache_diff::CacheDiff;
+
+struct OldMetadata {
+    version: String,
+}
+
+#[derive(CacheDiff)]
+#[cache_diff(compare_to = OldMetadata, removed = distro)]
+struct Metadata {
+    version: String,
+}
+
+fn main() {}
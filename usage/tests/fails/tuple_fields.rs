@@ -0,0 +1,6 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+struct Example(String);
+
+fn main() {}
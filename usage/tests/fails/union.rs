@@ -0,0 +1,9 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+union Example {
+    count: u32,
+    ratio: f32,
+}
+
+fn main() {}
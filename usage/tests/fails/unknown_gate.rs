@@ -0,0 +1,10 @@
+use cache_diff::CacheDiff;
+
+#[derive(CacheDiff)]
+#[cache_diff(gate = schema)]
+struct Metadata {
+    schema_version: u32,
+    version: String,
+}
+
+fn main() {}
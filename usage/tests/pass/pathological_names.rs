@@ -0,0 +1,89 @@
+use cache_diff::CacheDiff;
+
+// Shadow names the generated code could otherwise resolve to
+#[allow(dead_code)]
+struct Vec;
+#[allow(dead_code)]
+struct String;
+#[allow(unused_macros)]
+macro_rules! format {
+    ($($arg:tt)*) => {
+        panic!("user format! macro was called")
+    };
+}
+
+fn old(value: &bool) -> &'static str {
+    if *value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+#[derive(CacheDiff)]
+#[cache_diff(align_names)]
+struct Example {
+    r#fn: u32,
+    __private: u32,
+    differences: u32,
+    #[cache_diff(display = old)]
+    old: bool,
+    fmt_value: u32,
+    name_width: u32,
+    groups: u32,
+    weighted: u32,
+    names: u32,
+}
+
+impl Example {
+    // Inherent methods take precedence over trait methods in method call syntax
+    #[allow(dead_code)]
+    fn fmt_old_value(&self) {
+        panic!("inherent fmt_old_value was called")
+    }
+
+    #[allow(dead_code)]
+    fn fmt_new_value(&self) {
+        panic!("inherent fmt_new_value was called")
+    }
+}
+
+fn main() {
+    let now = Example {
+        r#fn: 2,
+        __private: 2,
+        differences: 2,
+        old: true,
+        fmt_value: 2,
+        name_width: 2,
+        groups: 2,
+        weighted: 2,
+        names: 2,
+    };
+    let diff = now.diff(&Example {
+        r#fn: 1,
+        __private: 1,
+        differences: 1,
+        old: false,
+        fmt_value: 1,
+        name_width: 1,
+        groups: 1,
+        weighted: 1,
+        names: 1,
+    });
+
+    assert_eq!(
+        diff,
+        vec![
+            "fn          (`1` to `2`)",
+            "  private   (`1` to `2`)",
+            "differences (`1` to `2`)",
+            "old         (`no` to `yes`)",
+            "fmt value   (`1` to `2`)",
+            "name width  (`1` to `2`)",
+            "groups      (`1` to `2`)",
+            "weighted    (`1` to `2`)",
+            "names       (`1` to `2`)",
+        ]
+    );
+}